@@ -37,9 +37,25 @@ pub fn derive_partial(input: proc_macro::TokenStream) -> proc_macro::TokenStream
         .unwrap()
         .2;
 
+    // the id keeps its type, so its serde attributes (like flatten) still apply
+    let id_attrs = match data {
+        syn::Data::Struct(ref s) => s
+            .fields
+            .iter()
+            .find(|field| field.ident.as_ref().is_some_and(|ident| ident == "id"))
+            .unwrap()
+            .attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("serde"))
+            .cloned()
+            .collect::<Vec<_>>(),
+        _ => unreachable!(),
+    };
+
     let field_var = fields.iter().map(|(vis, ident, ty)| {
         if ident.to_string() == "id" {
             quote! {
+                #(#id_attrs)*
                 #vis #ident: #ty
             }
         } else {
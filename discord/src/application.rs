@@ -1,16 +1,64 @@
+use derive_setters::Setters;
+use enumset::{EnumSet, EnumSetType};
 use partial_id::Partial;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::guild::GuildResource;
+use crate::permissions::Permissions;
 use crate::request::HttpRequest;
 use crate::resource::{resource, Endpoint};
 
-use super::{command::Commands, resource::Snowflake};
+use super::{
+    command::{Command, CommandData, Commands},
+    resource::Snowflake,
+};
 
 #[derive(Partial)]
 #[derive(Debug, Deserialize)]
 pub struct Application {
     pub id: Snowflake<Application>,
+    pub name: String,
+    pub description: String,
+    #[serde(default)]
+    pub flags: EnumSet<ApplicationFlag>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub install_params: Option<InstallParams>,
+}
+
+#[derive(EnumSetType, Debug)]
+pub enum ApplicationFlag {
+    AutoModerationRuleCreateBadge = 6,
+    GatewayPresence = 12,
+    GatewayPresenceLimited = 13,
+    GatewayGuildMembers = 14,
+    GatewayGuildMembersLimited = 15,
+    VerificationPendingGuildLimit = 16,
+    Embedded = 17,
+    GatewayMessageContent = 18,
+    GatewayMessageContentLimited = 19,
+    ApplicationCommandBadge = 23,
+}
+
+// the scopes and permissions used when adding the application to a server
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct InstallParams {
+    pub scopes: Vec<String>,
+    pub permissions: Permissions,
+}
+
+#[derive(Default, Setters, Serialize)]
+#[setters(strip_option)]
+pub struct PatchApplication {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tags: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    install_params: Option<InstallParams>,
+    // only the limited gateway intent flags can be changed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    flags: Option<EnumSet<ApplicationFlag>>,
 }
 
 pub trait ApplicationResource {
@@ -22,6 +70,15 @@ pub trait ApplicationResource {
     fn guild_commands(&self, guild: &impl GuildResource) -> Commands {
         Commands::new(self.endpoint().clone(), Some(guild.endpoint().clone()))
     }
+
+    #[resource(Vec<Command>)]
+    fn set_guild_commands(
+        &self,
+        guild: &impl GuildResource,
+        data: Vec<CommandData>,
+    ) -> HttpRequest<Vec<Command>> {
+        self.guild_commands(guild).set_request(data)
+    }
 }
 
 impl ApplicationResource for Snowflake<Application> {
@@ -49,4 +106,8 @@ impl Me {
     pub fn get(&self) -> HttpRequest<Application> {
         HttpRequest::get("/applications/@me")
     }
+    #[resource(Application)]
+    pub fn patch(&self, data: PatchApplication) -> HttpRequest<Application> {
+        HttpRequest::patch("/applications/@me", &data)
+    }
 }
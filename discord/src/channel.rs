@@ -2,7 +2,7 @@ use std::fmt::{Display, Formatter};
 use std::write;
 
 use partial_id::Partial;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::guild::Guild;
 use crate::resource::{resource, Endpoint};
@@ -11,6 +11,7 @@ use super::{
     message::{CreateMessage, Message},
     request::HttpRequest,
     resource::Snowflake,
+    user::User,
 };
 
 #[derive(Partial)]
@@ -21,6 +22,24 @@ pub struct Channel {
     pub name: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct Invite {
+    pub code: String,
+}
+
+impl Invite {
+    pub fn url(&self) -> String {
+        format!("https://discord.gg/{}", self.code)
+    }
+}
+
+#[derive(Serialize)]
+struct CreateInvite {
+    max_age: u32,
+    max_uses: u32,
+    unique: bool,
+}
+
 impl Display for Snowflake<Channel> {
     fn fmt(&self, f: &mut Formatter<'_>) -> ::std::fmt::Result {
         write!(f, "<#{}>", self.as_int())
@@ -44,6 +63,32 @@ pub trait ChannelResource {
     fn send_message(&self, data: CreateMessage) -> HttpRequest<Message> {
         HttpRequest::post_attached(format!("{}/messages", self.endpoint().uri()), &data)
     }
+    #[resource(Message)]
+    fn send_text(&self, content: &str) -> HttpRequest<Message> {
+        // plain text never has attachments
+        HttpRequest::post(
+            format!("{}/messages", self.endpoint().uri()),
+            &CreateMessage::default().content(content.into()),
+        )
+    }
+    #[resource(())]
+    fn add_thread_member(&self, user: Snowflake<User>) -> HttpRequest<()> {
+        HttpRequest::put(
+            format!("{}/thread-members/{}", self.endpoint().uri(), user.as_int()),
+            &serde_json::Map::new(),
+        )
+    }
+    #[resource(Invite)]
+    fn create_invite(&self, max_age: u32, max_uses: u32, unique: bool) -> HttpRequest<Invite> {
+        HttpRequest::post(
+            format!("{}/invites", self.endpoint().uri()),
+            &CreateInvite {
+                max_age,
+                max_uses,
+                unique,
+            },
+        )
+    }
 }
 
 impl ChannelResource for Snowflake<Channel> {
@@ -6,7 +6,9 @@ use crate::request::HttpRequest;
 use crate::resource::resource;
 use crate::resource::Endpoint;
 
-use super::{application::Application, guild::Guild, resource::Snowflake};
+use super::{
+    application::Application, guild::Guild, permissions::Permissions, resource::Snowflake,
+};
 
 #[derive(Debug, Deserialize, Copy, Clone)]
 pub struct Commands {
@@ -44,9 +46,51 @@ pub struct CommandData {
 
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub options: Vec<CommandOption>,
+
+    // members without these permissions cannot see the command, unless a server admin allows them
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    #[setters(strip_option)]
+    pub default_member_permissions: Option<Permissions>,
+}
+
+#[derive(Debug)]
+pub enum CommandError {
+    // names must be 1-32 lowercase characters
+    InvalidName(String),
+
+    // required options must come before optional ones
+    RequiredAfterOptional(String),
+}
+
+fn is_valid_name(name: &str) -> bool {
+    let len = name.chars().count();
+    (1..=32).contains(&len)
+        && name
+            .chars()
+            .all(|c| (c.is_alphanumeric() && !c.is_uppercase()) || c == '-' || c == '_')
 }
 
 impl CommandData {
+    pub fn validate(&self) -> Result<(), CommandError> {
+        if self.input_type == CommandType::ChatInput && !is_valid_name(&self.name) {
+            return Err(CommandError::InvalidName(self.name.clone()));
+        }
+
+        let mut optional = false;
+        for option in self.options.iter() {
+            let Some(name) = option.name() else {
+                continue;
+            };
+            if !is_valid_name(name) {
+                return Err(CommandError::InvalidName(name.into()));
+            }
+            if option.required() && optional {
+                return Err(CommandError::RequiredAfterOptional(name.into()));
+            }
+            optional |= !option.required();
+        }
+        Ok(())
+    }
     pub fn new<S1, S2>(name: S1, description: S2) -> Self
     where
         S1: Into<String>,
@@ -57,6 +101,7 @@ impl CommandData {
             description: description.into(),
             input_type: CommandType::ChatInput,
             options: Vec::new(),
+            default_member_permissions: None,
         }
     }
 }
@@ -71,7 +116,7 @@ pub enum CommandOption {
     #[serde(rename = 3)]
     String(StringOption),
     #[serde(rename = 4)]
-    Integer,
+    Integer(IntegerOption),
     #[serde(rename = 5)]
     Boolean,
     #[serde(rename = 6)]
@@ -118,6 +163,40 @@ impl StringOption {
     }
 }
 
+#[derive(Debug, Deserialize, Serialize, Setters)]
+#[setters(strip_option)]
+pub struct IntegerOption {
+    #[setters(skip)]
+    pub name: String,
+    #[setters(skip)]
+    pub description: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_value: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_value: Option<i64>,
+
+    #[serde(default)]
+    #[setters(bool)]
+    pub required: bool,
+}
+
+impl IntegerOption {
+    pub fn new<S1, S2>(name: S1, description: S2) -> Self
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            min_value: None,
+            max_value: None,
+            required: false,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Param<T> {
     pub name: String,
@@ -137,12 +216,35 @@ impl<T> Param<T> {
     }
 }
 
+impl CommandOption {
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            CommandOption::String(o) => Some(&o.name),
+            CommandOption::Integer(o) => Some(&o.name),
+            _ => None,
+        }
+    }
+    pub fn required(&self) -> bool {
+        match self {
+            CommandOption::String(o) => o.required,
+            CommandOption::Integer(o) => o.required,
+            _ => false,
+        }
+    }
+}
+
 impl From<StringOption> for CommandOption {
     fn from(value: StringOption) -> Self {
         Self::String(value)
     }
 }
 
+impl From<IntegerOption> for CommandOption {
+    fn from(value: IntegerOption) -> Self {
+        Self::Integer(value)
+    }
+}
+
 #[derive(Debug, Deserialize, Copy, Clone)]
 pub struct CommandIdentifier {
     #[serde(flatten)]
@@ -193,10 +295,32 @@ impl Commands {
     pub fn all(&self) -> HttpRequest<Vec<Command>> {
         HttpRequest::get(self.uri())
     }
+    // discord rejects invalid commands, use try_create to validate them first
     #[resource(Command)]
     pub fn create(&self, data: CommandData) -> HttpRequest<Command> {
         HttpRequest::post(self.uri(), &data)
     }
+    // replaces every command in this pool, commands not in data are removed
+    #[resource(Vec<Command>)]
+    pub fn set(&self, data: Vec<CommandData>) -> HttpRequest<Vec<Command>> {
+        HttpRequest::put(self.uri(), &data)
+    }
+    pub fn try_create(
+        &self,
+        data: CommandData,
+    ) -> std::result::Result<HttpRequest<Command>, CommandError> {
+        data.validate()?;
+        Ok(self.create_request(data))
+    }
+    pub fn try_set(
+        &self,
+        data: Vec<CommandData>,
+    ) -> std::result::Result<HttpRequest<Vec<Command>>, CommandError> {
+        for command in data.iter() {
+            command.validate()?;
+        }
+        Ok(self.set_request(data))
+    }
 }
 
 pub trait CommandResource: Sized {
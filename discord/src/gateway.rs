@@ -4,39 +4,61 @@ use std::{
     time::Duration,
 };
 
+use enumset::{EnumSet, EnumSetType};
 use futures_util::{
     future::{pending, Either},
     Future, SinkExt, Stream, StreamExt,
 };
 use rand::Rng;
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde::{de::IgnoredAny, Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 use tokio::{
     net::TcpStream,
     select,
-    sync::mpsc::{self, Sender},
+    sync::{
+        mpsc::{self, Sender},
+        watch,
+    },
     task::JoinHandle,
-    time::{interval_at, sleep_until, Instant, Interval},
+    time::{interval_at, sleep, sleep_until, timeout, Instant, Interval},
 };
 use tokio_stream::wrappers::ReceiverStream;
 use tokio_tungstenite::{
-    connect_async, tungstenite::Error, tungstenite::Message, MaybeTlsStream, WebSocketStream,
+    connect_async,
+    tungstenite::{
+        protocol::{frame::coding::CloseCode, CloseFrame},
+        Error, Message,
+    },
+    MaybeTlsStream, WebSocketStream,
 };
 
 use crate::request::Request;
 
 use super::request::{self, HttpRequest, RequestError};
-use super::{interaction::AnyInteraction, request::Bot};
+use super::{
+    application::PartialApplication,
+    channel::Channel,
+    guild::{Guild, Member},
+    interaction::AnyInteraction,
+    message::{Message as DiscordMessage, PartialMessage},
+    request::Bot,
+    resource::Snowflake,
+    user::User,
+    voice::VoiceState,
+};
 
 struct GatewayState {
     interval: Interval,
     heartbeat_timeout: Option<Instant>,
     ws_stream: WebSocketStream<MaybeTlsStream<TcpStream>>,
     sender: Sender<GatewayEvent>,
+    status: watch::Sender<GatewayStatus>,
     rx_die: ReceiverStream<()>,
+    rx_send: ReceiverStream<String>,
 
-    ready: Option<Ready>,
+    events: Option<Vec<String>>,
+    policy: ReconnectPolicy,
+    session: Option<Session>,
     sequence: Option<u32>,
     token: String,
 }
@@ -52,9 +74,42 @@ impl GatewayState {
         .unwrap();
 
         self.ws_stream.send(Message::text(message)).await?;
-        self.heartbeat_timeout = Some(Instant::now() + Duration::from_secs(2));
+
+        // connection is a zombie if no ack arrives before the next heartbeat
+        if self.heartbeat_timeout.is_none() {
+            self.heartbeat_timeout = Some(Instant::now() + self.interval.period());
+        }
         Ok(())
     }
+    fn wants(&self, event: Option<&str>) -> bool {
+        match (event, &self.events) {
+            // we always need the session information
+            (Some("READY"), _) => true,
+            (Some(event), Some(events)) => events.iter().any(|e| e == event),
+            (_, None) => true,
+            (None, _) => false,
+        }
+    }
+    async fn close(&mut self) {
+        // a normal close code invalidates the session
+        let frame = CloseFrame {
+            code: CloseCode::Normal,
+            reason: "".into(),
+        };
+        if self.ws_stream.close(Some(frame)).await.is_err() {
+            return;
+        }
+
+        // wait for the close handshake
+        let _ = timeout(CLOSE_TIMEOUT, async {
+            while let Some(Ok(message)) = self.ws_stream.next().await {
+                if message.is_close() {
+                    break;
+                }
+            }
+        })
+        .await;
+    }
     async fn run(&mut self) {
         loop {
             let timeout = match self.heartbeat_timeout {
@@ -64,12 +119,19 @@ impl GatewayState {
             select! {
                 _ = self.rx_die.next() => {
                     // manual close
+                    self.close().await;
                     break;
                 }
                 _ = timeout => {
                     // lost connection
                     break;
                 }
+                Some(message) = self.rx_send.next() => {
+                    // requests made by the consumer
+                    if self.ws_stream.send(Message::Text(message)).await.is_err() {
+                        break;
+                    }
+                }
                 _ = self.interval.tick() => {
                     // heartbeat!
                     if self.heartbeat().await.is_err() {
@@ -83,17 +145,29 @@ impl GatewayState {
                     };
                     match item {
                         Message::Text(s) => {
-                            let message: GatewayMessage<Value> = serde_json::from_str(&s).unwrap();
+                            // the payload is only parsed once we know we want it
+                            let message: GatewayMessage<IgnoredAny> = serde_json::from_str(&s).unwrap();
                             match message.op {
                                 GatewayOpcode::Dispatch => {
                                     // event happened
                                     self.sequence = message.s;
+                                    if message.t.as_deref() == Some("RESUMED") {
+                                        let _ = self.status.send(GatewayStatus::Resumed);
+                                    }
+                                    if !self.wants(message.t.as_deref()) {
+                                        continue;
+                                    }
                                     let event: std::result::Result<GatewayEvent, _> = serde_json::from_str(&s);
                                     match event {
-                                        Ok(GatewayEvent::Ready(ready)) => {
-                                            self.ready = Some(ready);
-                                        }
                                         Ok(event) => {
+                                            if let GatewayEvent::Ready(ready) = &event {
+                                                self.session = Some(Session {
+                                                    resume_gateway_url: ready.resume_gateway_url.clone(),
+                                                    session_id: ready.session_id.clone(),
+                                                });
+                                                let _ = self.status.send(GatewayStatus::Ready);
+                                            }
+
                                             if self.sender.send(event).await.is_err() {
                                                 // receiver is gone
                                                 break;
@@ -117,21 +191,26 @@ impl GatewayState {
                                 }
                                 GatewayOpcode::Reconnect => {
                                     // resume stream
-                                    let (Some(ready), Some(sequence)) = (&self.ready, self.sequence) else {
+                                    let (Some(session), Some(sequence)) = (&self.session, self.sequence) else {
                                         // we have no resume information
                                         break;
                                     };
 
-                                    let full_url = format!("{}/?v=10&encoding=json", ready.resume_gateway_url);
+                                    let _ = self.status.send(GatewayStatus::Reconnecting);
+                                    let full_url = format!("{}/?v=10&encoding=json", session.resume_gateway_url);
 
                                     self.ws_stream.close(None).await.expect("old websocket stream could not close");
-                                    (self.ws_stream, _) = connect_async(full_url).await.expect("could not connect");
+                                    let Some(ws_stream) = self.policy.connect(&full_url).await else {
+                                        // giving up, let the consumer decide what to do
+                                        break;
+                                    };
+                                    self.ws_stream = ws_stream;
 
                                     let resume = serde_json::to_string(&GatewayMessage {
                                         op: GatewayOpcode::Resume,
                                         d: Resume {
                                             token: &self.token,
-                                            session_id: &ready.session_id,
+                                            session_id: &session.session_id,
                                             seq: sequence,
                                         },
                                         s: None,
@@ -146,8 +225,8 @@ impl GatewayState {
                                 }
                                 GatewayOpcode::Hello => {
                                     // set heartbeat interval
-                                    let hello: std::result::Result<Hello, _> = serde_json::from_value(message.d);
-                                    if let Ok(hello) = hello {
+                                    let hello: std::result::Result<GatewayMessage<Hello>, _> = serde_json::from_str(&s);
+                                    if let Ok(GatewayMessage { d: hello, .. }) = hello {
                                         let heartbeat_interval = hello.heartbeat_interval;
                                         let offset = rand::thread_rng().gen_range(0..heartbeat_interval);
                                         let start = Instant::now() + Duration::from_millis(offset);
@@ -167,13 +246,88 @@ impl GatewayState {
             }
         }
         // TODO: reconnect?
+        let _ = self.status.send(GatewayStatus::Disconnected);
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    // None retries forever, Some(0) does not reconnect at all
+    pub max_attempts: Option<u32>,
+
+    // the delay doubles after every failed attempt, up to max_delay
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+
+    // randomize delays so many clients do not reconnect at once
+    pub jitter: bool,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: Some(5),
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            jitter: true,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    pub fn fail_fast() -> Self {
+        Self {
+            max_attempts: Some(0),
+            ..Default::default()
+        }
+    }
+    pub fn forever() -> Self {
+        Self {
+            max_attempts: None,
+            ..Default::default()
+        }
+    }
+    fn delay(&self, attempt: u32) -> Duration {
+        let delay = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        match self.jitter {
+            true => delay.mul_f64(rand::thread_rng().gen_range(0.5..1.0)),
+            false => delay,
+        }
+    }
+    async fn connect(&self, url: &str) -> Option<WebSocketStream<MaybeTlsStream<TcpStream>>> {
+        let mut attempt = 0;
+        while self.max_attempts.map_or(true, |max| attempt < max) {
+            if attempt > 0 {
+                sleep(self.delay(attempt - 1)).await;
+            }
+            match connect_async(url).await {
+                Ok((ws_stream, _)) => return Some(ws_stream),
+                Err(err) => println!("could not reconnect: {}", err),
+            }
+            attempt += 1;
+        }
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GatewayStatus {
+    Connecting,
+    Ready,
+    Resumed,
+    Reconnecting,
+    Disconnected,
+}
+
 pub struct Gateway {
     stream: ReceiverStream<GatewayEvent>,
+    status: watch::Receiver<GatewayStatus>,
     task: JoinHandle<()>,
     tx_die: Sender<()>,
+    tx_send: Sender<String>,
 }
 
 #[derive(Deserialize)]
@@ -188,6 +342,7 @@ enum GatewayOpcode {
     Heartbeat = 1,
     Identify = 2,
     Resume = 6,
+    RequestGuildMembers = 8,
     Reconnect = 7,
     InvalidSession = 9,
     Hello = 10,
@@ -209,6 +364,57 @@ struct GatewayMessage<T> {
 pub enum GatewayEvent {
     Ready(Ready),
     InteractionCreate(AnyInteraction),
+
+    // requires Intent::GuildVoiceStates
+    VoiceStateUpdate(VoiceState),
+
+    // requires Intent::GuildMessages or Intent::DirectMessages
+    MessageUpdate(PartialMessage),
+    MessageDelete {
+        id: Snowflake<DiscordMessage>,
+        channel_id: Snowflake<Channel>,
+    },
+
+    // response to Gateway::request_guild_members
+    GuildMembersChunk(GuildMembersChunk),
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GuildMembersChunk {
+    pub guild_id: Snowflake<Guild>,
+    pub members: Vec<Member>,
+    pub chunk_index: u32,
+    pub chunk_count: u32,
+}
+
+impl GuildMembersChunk {
+    // the amount of chunks received so far and the total amount of chunks
+    pub fn progress(&self) -> (u32, u32) {
+        (self.chunk_index + 1, self.chunk_count)
+    }
+    pub fn is_last(&self) -> bool {
+        self.chunk_index + 1 >= self.chunk_count
+    }
+}
+
+#[derive(EnumSetType, Debug)]
+pub enum Intent {
+    Guilds = 0,
+    GuildMembers = 1,
+    GuildModeration = 2,
+    GuildEmojisAndStickers = 3,
+    GuildIntegrations = 4,
+    GuildWebhooks = 5,
+    GuildInvites = 6,
+    GuildVoiceStates = 7,
+    GuildPresences = 8,
+    GuildMessages = 9,
+    GuildMessageReactions = 10,
+    GuildMessageTyping = 11,
+    DirectMessages = 12,
+    DirectMessageReactions = 13,
+    DirectMessageTyping = 14,
+    MessageContent = 15,
 }
 
 #[derive(Deserialize, Debug)]
@@ -232,10 +438,24 @@ struct ConnectionProperties {
 
 #[derive(Deserialize, Debug)]
 pub struct Ready {
+    pub user: User,
+    pub session_id: String,
+    pub application: PartialApplication,
+    resume_gateway_url: String,
+}
+
+struct Session {
     resume_gateway_url: String,
     session_id: String,
 }
 
+#[derive(Serialize, Debug)]
+struct RequestGuildMembers<'a> {
+    guild_id: Snowflake<Guild>,
+    query: &'a str,
+    limit: u32,
+}
+
 #[derive(Serialize, Debug)]
 struct Resume<'a> {
     token: &'a str,
@@ -245,6 +465,8 @@ struct Resume<'a> {
 
 const NAME: &str = env!("CARGO_PKG_NAME");
 
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
 impl Stream for Gateway {
     type Item = GatewayEvent;
 
@@ -262,7 +484,13 @@ impl Stream for Gateway {
 }
 
 impl Gateway {
-    pub async fn connect(client: &Bot) -> request::Result<Self> {
+    // events are dispatch names such as "INTERACTION_CREATE", None forwards every event
+    pub async fn connect(
+        client: &Bot,
+        intents: EnumSet<Intent>,
+        events: Option<&[&str]>,
+        policy: ReconnectPolicy,
+    ) -> request::Result<Self> {
         let GatewayResponse { url } = HttpRequest::get("/gateway").request(client).await?;
         let full_url = url + "/?v=10&encoding=json";
 
@@ -286,7 +514,7 @@ impl Gateway {
             op: GatewayOpcode::Identify,
             d: Identify {
                 token: client.token(),
-                intents: 0,
+                intents: intents.as_u32(),
                 properties: ConnectionProperties {
                     os: "linux",
                     browser: NAME,
@@ -308,6 +536,8 @@ impl Gateway {
 
         let (tx_event, rx_event) = mpsc::channel(16);
         let (tx_die, rx_die) = mpsc::channel(1);
+        let (tx_send, rx_send) = mpsc::channel(16);
+        let (tx_status, rx_status) = watch::channel(GatewayStatus::Connecting);
 
         let mut state = GatewayState {
             interval,
@@ -315,8 +545,12 @@ impl Gateway {
             heartbeat_timeout: None,
             ws_stream,
             rx_die: ReceiverStream::new(rx_die),
+            rx_send: ReceiverStream::new(rx_send),
             sender: tx_event,
-            ready: None,
+            status: tx_status,
+            events: events.map(|e| e.iter().map(|&e| e.into()).collect()),
+            policy,
+            session: None,
             token: client.token().into(),
         };
 
@@ -325,8 +559,31 @@ impl Gateway {
         Ok(Gateway {
             task,
             tx_die,
+            tx_send,
             stream: ReceiverStream::new(rx_event),
+            status: rx_status,
+        })
+    }
+
+    pub fn status(&self) -> watch::Receiver<GatewayStatus> {
+        self.status.clone()
+    }
+
+    // members arrive as GuildMembersChunk events, requires Intent::GuildMembers
+    pub async fn request_guild_members(&self, guild: Snowflake<Guild>) {
+        let message = serde_json::to_string(&GatewayMessage {
+            op: GatewayOpcode::RequestGuildMembers,
+            d: RequestGuildMembers {
+                guild_id: guild,
+                query: "",
+                limit: 0,
+            },
+            s: None,
+            t: None,
         })
+        .unwrap();
+
+        let _ = self.tx_send.send(message).await;
     }
 
     pub async fn next(&mut self) -> Option<GatewayEvent> {
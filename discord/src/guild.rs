@@ -1,10 +1,16 @@
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
 use partial_id::Partial;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
+use crate::cdn_image_url;
 use crate::channel::Channel;
+use crate::permissions::Permissions;
 use crate::request::HttpRequest;
 use crate::resource::resource;
 use crate::resource::Endpoint;
+use crate::user::User;
 
 use super::resource::Snowflake;
 
@@ -13,6 +19,95 @@ use super::resource::Snowflake;
 pub struct Guild {
     pub id: Snowflake<Guild>,
     pub name: String,
+    pub icon: Option<String>,
+    pub banner: Option<String>,
+}
+
+impl Guild {
+    pub fn icon_url(&self, size: u16) -> Option<String> {
+        let hash = self.icon.as_ref()?;
+        Some(cdn_image_url(
+            &format!("icons/{}", self.id.as_int()),
+            hash,
+            size,
+        ))
+    }
+    pub fn banner_url(&self, size: u16) -> Option<String> {
+        let hash = self.banner.as_ref()?;
+        Some(cdn_image_url(
+            &format!("banners/{}", self.id.as_int()),
+            hash,
+            size,
+        ))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Role {
+    pub id: Snowflake<Role>,
+    pub name: String,
+}
+
+impl Display for Snowflake<Role> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> ::std::fmt::Result {
+        write!(f, "<@&{}>", self.as_int())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Member {
+    pub user: Option<User>,
+    pub nick: Option<String>,
+
+    #[serde(default)]
+    pub roles: Vec<Snowflake<Role>>,
+
+    // only sent within interactions
+    pub permissions: Option<Permissions>,
+}
+
+impl Member {
+    pub fn display_name(&self) -> Option<&str> {
+        self.nick
+            .as_deref()
+            .or(self.user.as_ref().map(|u| u.username.as_str()))
+    }
+}
+
+#[derive(Serialize)]
+struct PatchMember {
+    // an ISO8601 timestamp, null removes the timeout
+    communication_disabled_until: Option<String>,
+}
+
+// discord allows timeouts of at most 28 days
+pub const MAX_TIMEOUT: Duration = Duration::from_secs(28 * 24 * 60 * 60);
+
+// formats a time as an ISO8601 timestamp in UTC
+fn timestamp(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let (days, secs) = (secs / 86400, secs % 86400);
+
+    // convert days since epoch to a civil date
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
 }
 
 impl Endpoint for Snowflake<Guild> {
@@ -32,6 +127,20 @@ pub trait GuildResource {
     fn get_channels(&self) -> HttpRequest<Vec<Channel>> {
         HttpRequest::get(format!("{}/channels", self.endpoint().uri()))
     }
+    #[resource(Member)]
+    fn timeout_member(&self, user: Snowflake<User>, until: Option<String>) -> HttpRequest<Member> {
+        HttpRequest::patch(
+            format!("{}/members/{}", self.endpoint().uri(), user.as_int()),
+            &PatchMember {
+                communication_disabled_until: until,
+            },
+        )
+    }
+    #[resource(Member)]
+    fn timeout_member_for(&self, user: Snowflake<User>, duration: Duration) -> HttpRequest<Member> {
+        let until = timestamp(SystemTime::now() + duration.min(MAX_TIMEOUT));
+        self.timeout_member_request(user, Some(until))
+    }
 }
 
 impl GuildResource for Snowflake<Guild> {
@@ -51,3 +160,31 @@ impl GuildResource for PartialGuild {
         self.id
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::timestamp;
+
+    fn at(secs: u64) -> String {
+        timestamp(UNIX_EPOCH + Duration::from_secs(secs))
+    }
+
+    #[test]
+    fn timestamp_epoch() {
+        assert_eq!(at(0), "1970-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn timestamp_leap_day() {
+        assert_eq!(at(1709210096), "2024-02-29T12:34:56Z");
+    }
+
+    #[test]
+    fn timestamp_2100() {
+        // 2100 is not a leap year
+        assert_eq!(at(4107542399), "2100-02-28T23:59:59Z");
+        assert_eq!(at(4107542400), "2100-03-01T00:00:00Z");
+    }
+}
@@ -1,10 +1,14 @@
-use std::{mem, sync::Arc};
+use std::{
+    mem,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use async_trait::async_trait;
 use derive_setters::Setters;
 use enumset::{EnumSet, EnumSetType};
 use isahc::{
-    http::{Method, StatusCode},
+    http::{HeaderMap, Method, StatusCode},
     AsyncReadResponseExt,
 };
 use monostate::MustBe;
@@ -13,8 +17,11 @@ use serde_json::Value;
 use serde_repr::Serialize_repr;
 
 use crate::{
-    message::{CreateAttachment, PartialAttachment},
-    request::{create_response, Attachments, Client, File, IndexedOr, Request, RequestError},
+    message::{ComponentType, CreateAttachment, PartialAttachment},
+    request::{
+        create_response, Attachments, Client, DiscordError, File, IndexedOr, Request, RequestError,
+        INVALID_WEBHOOK_TOKEN,
+    },
     resource::{resource, Endpoint},
 };
 
@@ -22,7 +29,8 @@ use super::{
     application::Application,
     channel::Channel,
     command::CommandIdentifier,
-    message::{ActionRow, Embed, Message, PatchMessage},
+    guild::{Guild, Member},
+    message::{ActionRow, AllowedMentions, Embed, LayoutComponent, Message, PatchMessage},
     request::{HttpRequest, Result},
     resource::Snowflake,
     user::User,
@@ -46,7 +54,11 @@ pub struct MessageInteraction<T: 'static> {
     pub user: User,
 
     pub channel_id: Snowflake<Channel>,
+    pub guild_id: Option<Snowflake<Guild>>,
     pub message: Message,
+
+    // only sent within guilds
+    pub member: Option<Member>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -58,6 +70,7 @@ pub struct Interaction<T: 'static> {
     pub user: User,
 
     pub channel_id: Snowflake<Channel>,
+    pub guild_id: Option<Snowflake<Guild>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -102,11 +115,32 @@ pub struct CreateReply {
 
     #[serde(skip_serializing_if = "Vec::is_empty")]
     embeds: Vec<Embed>,
+    // action rows, or any layout when using components v2
+    #[setters(skip)]
     #[serde(skip_serializing_if = "Vec::is_empty")]
-    components: Vec<ActionRow>,
+    components: Vec<LayoutComponent>,
 
     #[serde(skip_serializing_if = "EnumSet::is_empty")]
     flags: EnumSet<ReplyFlag>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    allowed_mentions: Option<AllowedMentions>,
+}
+
+impl CreateReply {
+    pub fn components(mut self, components: Vec<ActionRow>) -> Self {
+        self.components = components
+            .into_iter()
+            .map(LayoutComponent::ActionRow)
+            .collect();
+        self
+    }
+    // opts into components v2, which replace the content and embeds of the message
+    pub fn layout(mut self, layout: Vec<LayoutComponent>) -> Self {
+        self.components.extend(layout);
+        self.flags |= ReplyFlag::ComponentsV2;
+        self
+    }
 }
 
 #[derive(Default, Setters, Serialize)]
@@ -118,6 +152,9 @@ pub struct CreateUpdate {
     embeds: Vec<Embed>,
     components: Vec<ActionRow>,
     attachments: IndexedOr<CreateAttachment, PartialAttachment>,
+
+    #[serde(skip_serializing_if = "EnumSet::is_empty")]
+    flags: EnumSet<ReplyFlag>,
 }
 
 impl Attachments for CreateUpdate {
@@ -130,6 +167,7 @@ impl Attachments for CreateUpdate {
 pub enum ReplyFlag {
     Ephemeral = 6,
     SuppressEmbeds = 2,
+    ComponentsV2 = 15,
 }
 
 #[derive(Serialize)]
@@ -150,15 +188,22 @@ where
 
 pub struct Webhook;
 
+const INTERACTION_TOKEN_LIFETIME: Duration = Duration::from_secs(15 * 60);
+
 #[async_trait]
 impl Client for Webhook {
-    async fn request_weak<T: DeserializeOwned>(
+    fn retry_deadline(&self) -> Option<Duration> {
+        // interaction tokens are only valid for 15 minutes
+        Some(INTERACTION_TOKEN_LIFETIME)
+    }
+
+    async fn request_with_headers<T: DeserializeOwned>(
         &self,
         method: Method,
         uri: &str,
         body: Option<&str>,
         files: &[Arc<File>],
-    ) -> Result<T> {
+    ) -> Result<(T, HeaderMap)> {
         // send request
         let http = isahc::Request::builder()
             .method(method)
@@ -177,11 +222,26 @@ impl Client for Webhook {
             return Err(RequestError::RateLimited);
         }
 
+        let headers = response.headers().clone();
         let string = response.text().await.unwrap();
         // println!("{}", string);
 
         if response.status().is_client_error() {
-            return Err(RequestError::ClientError(response.status()));
+            let err: Option<DiscordError> = serde_json::from_str(&string).ok();
+            if err
+                .as_ref()
+                .is_some_and(|e| e.code == INVALID_WEBHOOK_TOKEN)
+            {
+                return Err(RequestError::Expired);
+            }
+            return Err(RequestError::ClientError(response.status(), err));
+        }
+
+        if matches!(
+            response.status(),
+            StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT
+        ) {
+            return Err(RequestError::Unavailable);
         }
 
         if response.status().is_server_error() {
@@ -193,6 +253,7 @@ impl Client for Webhook {
         } else {
             serde_json::from_str(&string)
         }
+        .map(|t| (t, headers))
         .map_err(|e| {
             println!("{}", e);
             RequestError::ServerError
@@ -201,6 +262,9 @@ impl Client for Webhook {
 }
 
 impl<T> InteractionToken<T> {
+    pub fn id(&self) -> Snowflake<Interaction<T>> {
+        self.id
+    }
     fn uri_response(mut self) -> String {
         let id = self.id;
         let token = mem::replace(&mut self.token, String::new());
@@ -210,6 +274,9 @@ impl<T> InteractionToken<T> {
 }
 
 impl<T> MessageInteractionToken<T> {
+    pub fn id(&self) -> Snowflake<MessageInteraction<T>> {
+        self.id
+    }
     fn uri_response(mut self) -> String {
         let id = self.id;
         let token = mem::replace(&mut self.token, String::new());
@@ -274,6 +341,7 @@ pub trait InteractionResource: Sized {
                 application_id,
                 token: str,
                 message: None,
+                created: Instant::now(),
             },
         )
     }
@@ -282,6 +350,23 @@ pub trait InteractionResource: Sized {
         let token = self.token();
         HttpRequest::post(token.uri_response(), &Response { typ: 9, data })
     }
+    #[resource(InteractionResponseIdentifier, client = Webhook)]
+    fn defer(self) -> ResponseRequest {
+        let token = self.token();
+        let application_id = token.application_id;
+        let str = token.token.clone();
+
+        // the response can be edited afterwards through the identifier
+        ResponseRequest(
+            HttpRequest::post(token.uri_response(), &Response { typ: 5, data: () }),
+            InteractionResponseIdentifier {
+                application_id,
+                token: str,
+                message: None,
+                created: Instant::now(),
+            },
+        )
+    }
 }
 
 pub trait MessageInteractionResource: Sized {
@@ -307,6 +392,7 @@ pub trait MessageInteractionResource: Sized {
                 application_id,
                 token: str,
                 message: None,
+                created: Instant::now(),
             },
         )
     }
@@ -328,6 +414,7 @@ pub trait MessageInteractionResource: Sized {
                 application_id,
                 token: str,
                 message: None,
+                created: Instant::now(),
             },
         )
     }
@@ -343,6 +430,7 @@ pub trait MessageInteractionResource: Sized {
                 application_id,
                 token: str,
                 message: None,
+                created: Instant::now(),
             },
         )
     }
@@ -353,16 +441,23 @@ pub struct InteractionResponseIdentifier {
     application_id: Snowflake<Application>,
     token: String,
     message: Option<Snowflake<Message>>,
+
+    // when the interaction token was first used
+    created: Instant,
 }
 
 impl InteractionResponseIdentifier {
+    pub fn is_expired(&self) -> bool {
+        self.created.elapsed() >= INTERACTION_TOKEN_LIFETIME
+    }
+
     #[resource(Message, client = Webhook)]
     pub fn get(&self) -> HttpRequest<Message, Webhook> {
         HttpRequest::get(self.uri())
     }
     #[resource(Message, client = Webhook)]
     pub fn patch(&self, data: PatchMessage) -> HttpRequest<Message, Webhook> {
-        HttpRequest::patch(self.uri(), &data)
+        HttpRequest::patch_attached(self.uri(), &data)
     }
     #[resource(Message, client = Webhook)]
     pub fn delete(self) -> HttpRequest<Message, Webhook> {
@@ -383,6 +478,7 @@ impl InteractionResponseIdentifier {
                 application_id,
                 token,
                 message: None,
+                created: self.created,
             },
         )
     }
@@ -405,6 +501,46 @@ impl Endpoint for InteractionResponseIdentifier {
     }
 }
 
+// a standalone webhook, its token does not expire like interaction tokens do
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct WebhookIdentifier {
+    id: Snowflake<WebhookIdentifier>,
+    token: String,
+}
+
+impl WebhookIdentifier {
+    pub fn new(id: Snowflake<WebhookIdentifier>, token: String) -> Self {
+        Self { id, token }
+    }
+
+    fn uri_message(&self, message: Snowflake<Message>) -> String {
+        format!("{}/messages/{}", self.uri(), message.as_int())
+    }
+
+    #[resource(Message, client = Webhook)]
+    pub fn get_message(&self, message: Snowflake<Message>) -> HttpRequest<Message, Webhook> {
+        HttpRequest::get(self.uri_message(message))
+    }
+    #[resource(Message, client = Webhook)]
+    pub fn edit_message(
+        &self,
+        message: Snowflake<Message>,
+        data: PatchMessage,
+    ) -> HttpRequest<Message, Webhook> {
+        HttpRequest::patch_attached(self.uri_message(message), &data)
+    }
+    #[resource((), client = Webhook)]
+    pub fn delete_message(&self, message: Snowflake<Message>) -> HttpRequest<(), Webhook> {
+        HttpRequest::delete(self.uri_message(message))
+    }
+}
+
+impl Endpoint for WebhookIdentifier {
+    fn uri(&self) -> String {
+        format!("/webhooks/{}/{}", self.id.as_int(), self.token)
+    }
+}
+
 impl<T> InteractionResource for InteractionToken<T> {
     type Data = T;
     fn token(self) -> InteractionToken<T> {
@@ -503,6 +639,9 @@ impl ParamValue {
     pub fn as_bool(&self) -> Option<bool> {
         self.value.as_bool()
     }
+    pub fn raw(&self) -> &Value {
+        &self.value
+    }
 }
 
 #[derive(Deserialize, Debug)]
@@ -519,9 +658,16 @@ pub struct ApplicationCommand {
     pub target: CommandTarget,
 }
 
+impl ApplicationCommand {
+    pub fn option(&self, name: &str) -> Option<&ParamValue> {
+        self.options.iter().find(|o| o.name == name)
+    }
+}
+
 #[derive(Deserialize, Debug)]
 pub struct MessageComponent {
     pub custom_id: String,
+    pub component_type: ComponentType,
 
     #[serde(default)]
     pub values: Vec<String>,
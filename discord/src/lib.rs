@@ -10,7 +10,9 @@ pub mod command;
 pub mod guild;
 pub mod interaction;
 pub mod message;
+pub mod permissions;
 pub mod user;
+pub mod voice;
 
 pub struct EscapedChars<T: Iterator<Item = char>>(T, Option<char>);
 
@@ -42,6 +44,36 @@ pub fn escape_string(s: &str) -> String {
     EscapedChars::new(s.chars()).collect()
 }
 
+// cuts a string down to at most max characters, ending it with an ellipsis if anything was cut
+pub fn truncate_escaped(s: &str, max: usize) -> String {
+    let Some((end, _)) = s.char_indices().nth(max) else {
+        return s.into();
+    };
+
+    // make room for the ellipsis
+    let end = s[..end].char_indices().last().map_or(0, |(i, _)| i);
+    let mut cut = &s[..end];
+
+    // an odd amount of trailing backslashes would escape the ellipsis
+    if cut.chars().rev().take_while(|&c| c == '\\').count() % 2 == 1 {
+        cut = &cut[..cut.len() - 1];
+    }
+    format!("{}…", cut)
+}
+
+// builds a cdn url for an image hash, sizes must be a power of two between 16 and 4096
+pub(crate) fn cdn_image_url(path: &str, hash: &str, size: u16) -> String {
+    // animated images have a hash starting with "a_"
+    let ext = match hash.starts_with("a_") {
+        true => "gif",
+        false => "png",
+    };
+    format!(
+        "https://cdn.discordapp.com/{}/{}.{}?size={}",
+        path, hash, ext, size
+    )
+}
+
 #[derive(PartialEq, Eq)]
 pub enum InlineCodeState {
     None,
@@ -53,14 +85,31 @@ pub enum InlineCodeState {
 pub struct DiscordFormatter<'a> {
     fmt: &'a mut (dyn Write + 'a),
     state: InlineCodeState,
+
+    // whether the last character written inside code was a backtick
+    backtick: bool,
 }
 
+// separates backticks so they never form a run that closes the code fence
+const ZERO_WIDTH_SPACE: char = '\u{200B}';
+
 impl<'a> DiscordFormatter<'a> {
     pub fn new(fmt: &'a mut (dyn Write + 'a)) -> Self {
         Self {
             fmt,
             state: InlineCodeState::None,
+            backtick: false,
+        }
+    }
+    fn write_code(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            if c == '`' && self.backtick {
+                self.fmt.write_char(ZERO_WIDTH_SPACE)?;
+            }
+            self.fmt.write_char(c)?;
+            self.backtick = c == '`';
         }
+        Ok(())
     }
     pub fn start_code(&mut self) -> fmt::Result {
         match self.state {
@@ -85,6 +134,9 @@ impl<'a> DiscordFormatter<'a> {
                 Ok(())
             }
             InlineCodeState::Inside => {
+                if self.backtick {
+                    self.fmt.write_char(ZERO_WIDTH_SPACE)?;
+                }
                 self.fmt.write_str("``")?;
                 self.state = InlineCodeState::Ended;
                 Ok(())
@@ -109,12 +161,13 @@ impl Write for DiscordFormatter<'_> {
             InlineCodeState::Starting => {
                 if !s.is_empty() {
                     self.fmt.write_str("``")?;
-                    self.fmt.write_str(s)?;
+                    self.backtick = true;
+                    self.write_code(s)?;
                     self.state = InlineCodeState::Inside
                 }
                 Ok(())
             }
-            InlineCodeState::Inside => self.fmt.write_str(s),
+            InlineCodeState::Inside => self.write_code(s),
             InlineCodeState::Ended => {
                 if !s.is_empty() {
                     for c in EscapedChars::new(s.chars()) {
@@ -131,3 +184,35 @@ impl Write for DiscordFormatter<'_> {
 pub trait DisplayDiscord {
     fn fmt(&self, f: &mut DiscordFormatter<'_>) -> fmt::Result;
 }
+
+// mentions are written as is, so they are not escaped or put inside code
+impl<T> DisplayDiscord for resource::Snowflake<T>
+where
+    resource::Snowflake<T>: fmt::Display,
+{
+    fn fmt(&self, f: &mut DiscordFormatter<'_>) -> fmt::Result {
+        write!(f.unescaped(), "{}", self)
+    }
+}
+
+// these only ping anyone if allowed by the allowed mentions of the message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mention {
+    Everyone,
+    Here,
+}
+
+impl fmt::Display for Mention {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mention::Everyone => f.write_str("@everyone"),
+            Mention::Here => f.write_str("@here"),
+        }
+    }
+}
+
+impl DisplayDiscord for Mention {
+    fn fmt(&self, f: &mut DiscordFormatter<'_>) -> fmt::Result {
+        write!(f.unescaped(), "{}", self)
+    }
+}
@@ -3,19 +3,24 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use derive_setters::Setters;
+use enumset::{EnumSet, EnumSetType};
 use monostate::{MustBe, MustBeU64};
 use partial_id::Partial;
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 
 use crate::channel::ChannelResource;
-use crate::guild::Guild;
+use crate::guild::{Guild, Role};
 use crate::request::{Attachments, Bot, File, Indexed, IndexedOr};
 use crate::resource::{resource, Endpoint};
 
 use super::request::HttpRequest;
 use super::request::Result;
-use super::{channel::Channel, resource::Snowflake, user::PartialUser};
+use super::{
+    channel::Channel,
+    resource::Snowflake,
+    user::{PartialUser, User},
+};
 
 #[derive(Debug, Deserialize, Copy, Clone, PartialEq, Eq)]
 pub struct MessageIdentifier {
@@ -26,6 +31,12 @@ pub struct MessageIdentifier {
 }
 
 impl MessageIdentifier {
+    pub fn new(channel: Snowflake<Channel>, message: Snowflake<Message>) -> Self {
+        Self {
+            channel_id: channel,
+            message_id: message,
+        }
+    }
     pub fn snowflake(&self) -> Snowflake<Message> {
         self.message_id
     }
@@ -40,10 +51,7 @@ pub struct MessageLink {
 
 impl MessageLink {
     pub fn message(&self) -> MessageIdentifier {
-        MessageIdentifier {
-            channel_id: self.channel_id,
-            message_id: self.message_id,
-        }
+        MessageIdentifier::new(self.channel_id, self.message_id)
     }
 }
 
@@ -103,11 +111,58 @@ pub struct CreateMessage {
 
     #[serde(skip_serializing_if = "Vec::is_empty")]
     embeds: Vec<Embed>,
+    // action rows, or any layout when using components v2
+    #[setters(skip)]
     #[serde(skip_serializing_if = "Vec::is_empty")]
-    components: Vec<ActionRow>,
+    components: Vec<LayoutComponent>,
 
     #[serde(skip_serializing_if = "Indexed::is_empty")]
     attachments: Indexed<CreateAttachment>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    allowed_mentions: Option<AllowedMentions>,
+
+    #[serde(skip_serializing_if = "EnumSet::is_empty")]
+    flags: EnumSet<MessageFlag>,
+}
+
+impl CreateMessage {
+    pub fn components(mut self, components: Vec<ActionRow>) -> Self {
+        self.components = components
+            .into_iter()
+            .map(LayoutComponent::ActionRow)
+            .collect();
+        self
+    }
+    // opts into components v2, which replace the content and embeds of the message
+    pub fn layout(mut self, layout: Vec<LayoutComponent>) -> Self {
+        self.components.extend(layout);
+        self.flags |= MessageFlag::ComponentsV2;
+        self
+    }
+}
+
+#[derive(EnumSetType, Debug)]
+pub enum MessageFlag {
+    SuppressEmbeds = 2,
+    ComponentsV2 = 15,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MentionType {
+    Roles,
+    Users,
+    // both @everyone and @here
+    Everyone,
+}
+
+// mentions that are not allowed here are still shown, but do not ping anyone
+#[derive(Debug, Default, Setters, Serialize)]
+pub struct AllowedMentions {
+    parse: Vec<MentionType>,
+    roles: Vec<Snowflake<Role>>,
+    users: Vec<Snowflake<User>>,
 }
 
 impl Attachments for CreateMessage {
@@ -149,6 +204,18 @@ pub struct PatchMessage {
     attachments: IndexedOr<CreateAttachment, PartialAttachment>,
 }
 
+impl PatchMessage {
+    // existing attachments not kept here are removed from the message
+    pub fn keep_attachment(mut self, attachment: impl Into<PartialAttachment>) -> Self {
+        self.attachments.1.push(attachment.into());
+        self
+    }
+    pub fn add_attachment(mut self, attachment: CreateAttachment) -> Self {
+        self.attachments.0.push(attachment);
+        self
+    }
+}
+
 impl Attachments for PatchMessage {
     fn attachments(&self) -> Vec<Arc<File>> {
         self.attachments.0.iter().map(|a| a.file.clone()).collect()
@@ -168,7 +235,66 @@ pub struct Embed {
     pub fields: Vec<Field>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug)]
+pub enum EmbedError {
+    // the amount of characters in each part of the embed that was too long
+    Title(usize),
+    Description(usize),
+    AuthorName(usize),
+    FieldName { index: usize, len: usize },
+    FieldValue { index: usize, len: usize },
+
+    FieldCount(usize),
+    Total(usize),
+}
+
+const EMBED_TITLE_LIMIT: usize = 256;
+const EMBED_DESCRIPTION_LIMIT: usize = 4096;
+const EMBED_FIELD_COUNT_LIMIT: usize = 25;
+const FIELD_NAME_LIMIT: usize = 256;
+const AUTHOR_NAME_LIMIT: usize = 256;
+const EMBED_TOTAL_LIMIT: usize = 6000;
+
+impl Embed {
+    pub fn checked_build(self) -> std::result::Result<Embed, EmbedError> {
+        let len = |s: &Option<String>| s.as_ref().map_or(0, |s| s.chars().count());
+
+        let title = len(&self.title);
+        if title > EMBED_TITLE_LIMIT {
+            return Err(EmbedError::Title(title));
+        }
+        let description = len(&self.description);
+        if description > EMBED_DESCRIPTION_LIMIT {
+            return Err(EmbedError::Description(description));
+        }
+        let author = self.author.as_ref().map_or(0, |a| a.name.chars().count());
+        if author > AUTHOR_NAME_LIMIT {
+            return Err(EmbedError::AuthorName(author));
+        }
+        if self.fields.len() > EMBED_FIELD_COUNT_LIMIT {
+            return Err(EmbedError::FieldCount(self.fields.len()));
+        }
+
+        let mut total = title + description + author;
+        for (index, field) in self.fields.iter().enumerate() {
+            let name = field.name.chars().count();
+            if name > FIELD_NAME_LIMIT {
+                return Err(EmbedError::FieldName { index, len: name });
+            }
+            let value = field.value.chars().count();
+            if value > FIELD_VALUE_LIMIT {
+                return Err(EmbedError::FieldValue { index, len: value });
+            }
+            total += name + value;
+        }
+        if total > EMBED_TOTAL_LIMIT {
+            return Err(EmbedError::Total(total));
+        }
+        Ok(self)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ActionRow {
     #[serde(rename = "type")]
     typ: MustBe!(1u64),
@@ -184,7 +310,7 @@ impl ActionRow {
     }
     pub fn is_full(&self) -> bool {
         if self.components.len() >= 5 {
-            return false;
+            return true;
         }
         return match self.components.first() {
             Some(ActionRowComponent::Button(_)) => false,
@@ -194,6 +320,107 @@ impl ActionRow {
     }
 }
 
+// top level components, anything other than action rows requires components v2
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum LayoutComponent {
+    ActionRow(ActionRow),
+    TextDisplay(TextDisplay),
+    Separator(Separator),
+    Container(Container),
+}
+
+impl From<ActionRow> for LayoutComponent {
+    fn from(value: ActionRow) -> Self {
+        Self::ActionRow(value)
+    }
+}
+
+impl From<TextDisplay> for LayoutComponent {
+    fn from(value: TextDisplay) -> Self {
+        Self::TextDisplay(value)
+    }
+}
+
+impl From<Separator> for LayoutComponent {
+    fn from(value: Separator) -> Self {
+        Self::Separator(value)
+    }
+}
+
+impl From<Container> for LayoutComponent {
+    fn from(value: Container) -> Self {
+        Self::Container(value)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TextDisplay {
+    #[serde(rename = "type")]
+    typ: MustBe!(10u64),
+    pub content: String,
+}
+
+impl TextDisplay {
+    pub fn new<S>(content: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            typ: MustBeU64,
+            content: content.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum SeparatorSpacing {
+    Small = 1,
+    Large = 2,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Separator {
+    #[serde(rename = "type")]
+    typ: MustBe!(14u64),
+    pub divider: bool,
+    pub spacing: SeparatorSpacing,
+}
+
+impl Separator {
+    pub fn new(divider: bool, spacing: SeparatorSpacing) -> Self {
+        Self {
+            typ: MustBeU64,
+            divider,
+            spacing,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Container {
+    #[serde(rename = "type")]
+    typ: MustBe!(17u64),
+    pub components: Vec<LayoutComponent>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub accent_color: Option<u32>,
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub spoiler: bool,
+}
+
+impl Container {
+    pub fn new(components: Vec<LayoutComponent>) -> Self {
+        Self {
+            typ: MustBeU64,
+            components,
+            accent_color: None,
+            spoiler: false,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize_repr, Deserialize_repr)]
 #[repr(u8)]
 pub enum ButtonStyle {
@@ -203,7 +430,7 @@ pub enum ButtonStyle {
     Danger = 4,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum Button {
     Action {
@@ -226,7 +453,7 @@ const fn _default_1() -> usize {
     1
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TextSelectMenu {
     pub custom_id: String,
     pub options: Vec<SelectOption>,
@@ -239,7 +466,48 @@ pub struct TextSelectMenu {
     pub disabled: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug)]
+pub enum SelectMenuError {
+    // menus must have between 1 and 25 options
+    OptionCount(usize),
+
+    // values must satisfy min <= max <= option count
+    ValueRange { min: usize, max: usize },
+}
+
+impl TextSelectMenu {
+    pub fn validate(&self) -> std::result::Result<(), SelectMenuError> {
+        let count = self.options.len();
+        if !(1..=25).contains(&count) {
+            return Err(SelectMenuError::OptionCount(count));
+        }
+        if self.max_values == 0 || self.min_values > self.max_values || self.max_values > count {
+            return Err(SelectMenuError::ValueRange {
+                min: self.min_values,
+                max: self.max_values,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize_repr)]
+#[repr(u8)]
+pub enum ComponentType {
+    ActionRow = 1,
+    Button = 2,
+    TextSelectMenu = 3,
+    TextInput = 4,
+    UserSelectMenu = 5,
+    RoleSelectMenu = 6,
+    MentionableSelectMenu = 7,
+    ChannelSelectMenu = 8,
+    TextDisplay = 10,
+    Separator = 14,
+    Container = 17,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum ActionRowComponent {
     #[serde(rename = 2)]
@@ -248,7 +516,7 @@ pub enum ActionRowComponent {
     TextSelectMenu(TextSelectMenu),
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SelectOption {
     pub label: String,
     pub value: String,
@@ -271,7 +539,10 @@ impl Author {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+// the maximum amount of characters in a field value
+pub const FIELD_VALUE_LIMIT: usize = 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Field {
     pub name: String,
     pub value: String,
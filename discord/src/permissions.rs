@@ -0,0 +1,88 @@
+use std::num::ParseIntError;
+
+use enumset::{EnumSet, EnumSetType};
+use serde::{Deserialize, Serialize};
+
+#[derive(EnumSetType, Debug)]
+pub enum Permission {
+    CreateInstantInvite = 0,
+    KickMembers = 1,
+    BanMembers = 2,
+    Administrator = 3,
+    ManageChannels = 4,
+    ManageGuild = 5,
+    AddReactions = 6,
+    ViewAuditLog = 7,
+    PrioritySpeaker = 8,
+    Stream = 9,
+    ViewChannel = 10,
+    SendMessages = 11,
+    SendTtsMessages = 12,
+    ManageMessages = 13,
+    EmbedLinks = 14,
+    AttachFiles = 15,
+    ReadMessageHistory = 16,
+    MentionEveryone = 17,
+    UseExternalEmojis = 18,
+    ViewGuildInsights = 19,
+    Connect = 20,
+    Speak = 21,
+    MuteMembers = 22,
+    DeafenMembers = 23,
+    MoveMembers = 24,
+    UseVad = 25,
+    ChangeNickname = 26,
+    ManageNicknames = 27,
+    ManageRoles = 28,
+    ManageWebhooks = 29,
+    ManageGuildExpressions = 30,
+    UseApplicationCommands = 31,
+    RequestToSpeak = 32,
+    ManageEvents = 33,
+    ManageThreads = 34,
+    CreatePublicThreads = 35,
+    CreatePrivateThreads = 36,
+    UseExternalStickers = 37,
+    SendMessagesInThreads = 38,
+    UseEmbeddedActivities = 39,
+    ModerateMembers = 40,
+}
+
+// Discord sends permissions as a string, since the bitfield may not fit in a json number
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Permissions(pub EnumSet<Permission>);
+
+impl Permissions {
+    pub fn contains(&self, permission: Permission) -> bool {
+        // administrators implicitly have every permission
+        self.0.contains(Permission::Administrator) || self.0.contains(permission)
+    }
+}
+
+impl From<EnumSet<Permission>> for Permissions {
+    fn from(value: EnumSet<Permission>) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Permission> for Permissions {
+    fn from(value: Permission) -> Self {
+        Self(value.into())
+    }
+}
+
+impl From<Permissions> for String {
+    fn from(value: Permissions) -> Self {
+        value.0.as_u64().to_string()
+    }
+}
+
+impl TryFrom<String> for Permissions {
+    type Error = ParseIntError;
+
+    fn try_from(value: String) -> ::std::result::Result<Self, Self::Error> {
+        // unknown permissions are ignored
+        Ok(Self(EnumSet::from_u64_truncated(value.parse()?)))
+    }
+}
@@ -1,9 +1,12 @@
-use std::{collections::HashMap, marker::PhantomData, sync::Arc, time::Duration};
+use std::{
+    collections::HashMap, io, marker::PhantomData, path::PathBuf, sync::Arc, time::Duration,
+};
 
 use async_trait::async_trait;
+use futures_util::io::{AllowStdIo, AsyncRead, AsyncReadExt, Cursor};
 use isahc::{
-    http::{Method, StatusCode},
-    AsyncReadResponseExt,
+    http::{HeaderMap, Method, StatusCode},
+    AsyncBody, AsyncReadResponseExt,
 };
 use serde::{de::DeserializeOwned, ser::SerializeSeq, Deserialize, Serialize};
 use tokio::{sync::Mutex, time::Instant};
@@ -63,18 +66,49 @@ pub enum RequestError {
     // 429 response
     RateLimited,
 
-    // 4xx reponse
-    ClientError(StatusCode),
+    // 4xx reponse, with the error Discord sent if it could be parsed
+    ClientError(StatusCode, Option<DiscordError>),
+
+    // 502, 503 or 504 response (these errors could be retried after a while)
+    Unavailable,
 
     // 5xx response, unexpected response
     ServerError,
 
     // gateway error
     InvalidSession,
+
+    // interaction token is older than 15 minutes
+    Expired,
+
+    // bot token is malformed, checked before any request is sent
+    InvalidToken,
 }
 
 pub type Result<T> = ::std::result::Result<T, RequestError>;
 
+// https://discord.com/developers/docs/topics/opcodes-and-status-codes#json
+pub const INTERACTION_ALREADY_ACKNOWLEDGED: u64 = 40060;
+pub const INVALID_WEBHOOK_TOKEN: u64 = 50027;
+
+#[derive(Debug, Deserialize)]
+pub struct DiscordError {
+    pub code: u64,
+    pub message: String,
+}
+
+impl RequestError {
+    pub fn code(&self) -> Option<u64> {
+        match self {
+            RequestError::ClientError(_, Some(err)) => Some(err.code),
+            _ => None,
+        }
+    }
+    pub fn is_already_acknowledged(&self) -> bool {
+        self.code() == Some(INTERACTION_ALREADY_ACKNOWLEDGED)
+    }
+}
+
 impl<T, C> HttpRequest<T, C>
 where
     T: DeserializeOwned,
@@ -145,6 +179,19 @@ where
         }
     }
 
+    pub fn put<S>(uri: S, body: &impl Serialize) -> Self
+    where
+        S: Into<String>,
+    {
+        HttpRequest {
+            phantom: PhantomData,
+            method: Method::PUT,
+            uri: uri.into(),
+            body: Some(serde_json::to_string(body).unwrap()),
+            files: Vec::new(),
+        }
+    }
+
     pub fn delete<S>(uri: S) -> Self
     where
         S: Into<String>,
@@ -171,6 +218,8 @@ struct DiscordRateLimits {
 #[derive(Clone)]
 pub struct Bot {
     token: String,
+    user_agent: String,
+    retry_deadline: Option<Duration>,
     limits: Arc<Mutex<DiscordRateLimits>>,
 }
 
@@ -186,6 +235,9 @@ struct RateLimitResponse {
 
 const GLOBAL_RATE_LIMIT: f32 = 45.0;
 
+const UNAVAILABLE_BACKOFF: Duration = Duration::from_millis(500);
+const UNAVAILABLE_BACKOFF_MAX: Duration = Duration::from_secs(8);
+
 impl DiscordRateLimits {
     fn inc_request(&mut self) {
         let now = Instant::now();
@@ -280,18 +332,58 @@ pub trait Attachments {
 pub struct File {
     pub name: String,
     pub typ: String,
-    pub data: Box<[u8]>,
+    pub data: FileSource,
+}
+
+pub enum FileSource {
+    Memory(Box<[u8]>),
+    // read while uploading, so the file is never fully loaded into memory
+    Path(PathBuf),
+}
+
+impl File {
+    pub fn from_bytes(name: String, typ: String, data: impl Into<Box<[u8]>>) -> Self {
+        Self {
+            name,
+            typ,
+            data: FileSource::Memory(data.into()),
+        }
+    }
+    pub fn from_path(name: String, typ: String, path: impl Into<PathBuf>) -> Self {
+        Self {
+            name,
+            typ,
+            data: FileSource::Path(path.into()),
+        }
+    }
 }
 
 #[async_trait]
 pub trait Client: Sync {
+    async fn request_with_headers<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        uri: &str,
+        body: Option<&str>,
+        files: &[Arc<File>],
+    ) -> Result<(T, HeaderMap)>;
+
+    // maximum time spent retrying a single request, if any
+    fn retry_deadline(&self) -> Option<Duration> {
+        None
+    }
+
     async fn request_weak<T: DeserializeOwned>(
         &self,
         method: Method,
         uri: &str,
         body: Option<&str>,
         files: &[Arc<File>],
-    ) -> Result<T>;
+    ) -> Result<T> {
+        self.request_with_headers(method, uri, body, files)
+            .await
+            .map(|(t, _)| t)
+    }
 
     async fn request<T: DeserializeOwned>(
         &self,
@@ -300,11 +392,25 @@ pub trait Client: Sync {
         body: Option<&str>,
         files: &[Arc<File>],
     ) -> Result<T> {
+        // dropping this future cancels any retries, nothing is spawned
+        let start = Instant::now();
+        let deadline = self.retry_deadline();
+        let mut backoff = UNAVAILABLE_BACKOFF;
         loop {
-            match self.request_weak(method.clone(), uri, body, files).await {
-                Err(RequestError::RateLimited) => (),
-                Err(RequestError::Network) => (),
-                r => break r,
+            let r = self.request_weak(method.clone(), uri, body, files).await;
+            let retry = matches!(
+                r,
+                Err(RequestError::RateLimited | RequestError::Network | RequestError::Unavailable)
+            );
+
+            // give up with the last error once the deadline has passed
+            if !retry || deadline.is_some_and(|d| start.elapsed() >= d) {
+                break r;
+            }
+
+            if let Err(RequestError::Unavailable) = r {
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(UNAVAILABLE_BACKOFF_MAX);
             }
         }
     }
@@ -314,6 +420,8 @@ impl Bot {
     pub fn new<S: Into<String>>(token: S) -> Self {
         Self {
             token: token.into(),
+            user_agent: format!("DiscordBot ({}, {})", "https://astavie.github.io/", VERSION),
+            retry_deadline: None,
             limits: Arc::new(Mutex::new(DiscordRateLimits {
                 request_rate: 0.0,
                 last_request: Instant::now(),
@@ -340,43 +448,113 @@ impl Bot {
         }
     }
 
+    pub fn try_new<S: Into<String>>(token: S) -> Result<Self> {
+        let token = token.into();
+        if !Bot::is_valid_token(&token) {
+            return Err(RequestError::InvalidToken);
+        }
+        Ok(Bot::new(token))
+    }
+    fn is_valid_token(token: &str) -> bool {
+        // tokens consist of three base64 segments separated by dots
+        let segments: Vec<_> = token.split('.').collect();
+        segments.len() == 3
+            && segments.iter().all(|s| {
+                !s.is_empty()
+                    && s.chars().all(|c| {
+                        c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '+' | '/' | '=')
+                    })
+            })
+    }
+    pub fn with_user_agent(mut self, user_agent: String) -> Self {
+        self.user_agent = user_agent;
+        self
+    }
+    pub fn with_retry_deadline(mut self, deadline: Duration) -> Self {
+        self.retry_deadline = Some(deadline);
+        self
+    }
+
     pub fn token(&self) -> &str {
         &self.token
     }
 }
 
+struct FileData(Arc<File>);
+
+impl AsRef<[u8]> for FileData {
+    fn as_ref(&self) -> &[u8] {
+        match &self.0.data {
+            FileSource::Memory(data) => data,
+            FileSource::Path(_) => unreachable!("files on disk are read by file_reader"),
+        }
+    }
+}
+
+type BodyReader = Box<dyn AsyncRead + Send + Sync + Unpin>;
+
+fn file_reader(file: &Arc<File>) -> io::Result<(BodyReader, usize)> {
+    match &file.data {
+        FileSource::Memory(data) => Ok((Box::new(Cursor::new(FileData(file.clone()))), data.len())),
+        FileSource::Path(path) => {
+            // the file is opened again for every retry
+            let f = std::fs::File::open(path)?;
+            let len = f.metadata()?.len() as usize;
+            Ok((Box::new(AllowStdIo::new(f)), len))
+        }
+    }
+}
+
+fn multipart_body(body: Option<&str>, files: &[Arc<File>]) -> io::Result<(BodyReader, u64)> {
+    // the file data is streamed from the shared buffers instead of being copied into one body
+    let mut length = 0;
+    let mut reader: BodyReader = Box::new(Cursor::new(Vec::new()));
+    let mut append = |reader: BodyReader, part: BodyReader, len: usize| -> BodyReader {
+        length += len as u64;
+        Box::new(reader.chain(part))
+    };
+
+    if let Some(body) = body {
+        let part = format!(
+            "--boundary\nContent-Disposition: form-data; name=\"payload_json\"\nContent-Type: application/json\n\n{}\n",
+            body
+        );
+        let len = part.len();
+        reader = append(reader, Box::new(Cursor::new(part)), len);
+    }
+
+    for (i, file) in files.iter().enumerate() {
+        let header = format!(
+            "--boundary\nContent-Disposition: form-data; name=\"files[{}]\"; filename=\"{}\"\nContent-Type: {}\n\n",
+            i,
+            file.name,
+            file.typ,
+        );
+        let len = header.len();
+        reader = append(reader, Box::new(Cursor::new(header)), len);
+
+        let (data, len) = file_reader(file)?;
+        reader = append(reader, data, len);
+        reader = append(reader, Box::new(Cursor::new("\n")), 1);
+    }
+
+    let end = "--boundary--\n";
+    reader = append(reader, Box::new(Cursor::new(end)), end.len());
+
+    Ok((reader, length))
+}
+
 pub async fn create_response(
     http: isahc::http::request::Builder,
     body: Option<&str>,
     files: &[Arc<File>],
 ) -> std::result::Result<isahc::Response<isahc::AsyncBody>, isahc::Error> {
     if files.len() > 0 {
-        let mut bytes = Vec::new();
-
-        if let Some(body) = body {
-            bytes.extend_from_slice(
-                "--boundary\nContent-Disposition: form-data; name=\"payload_json\"\nContent-Type: application/json\n\n"
-                .as_bytes(),
-            );
-            bytes.extend_from_slice(body.as_bytes());
-            bytes.extend_from_slice("\n".as_bytes());
-        }
-
-        for (i, file) in files.iter().enumerate() {
-            bytes.extend_from_slice(format!(
-                "--boundary\nContent-Disposition: form-data; name=\"files[{}]\"; filename=\"{}\"\nContent-Type: {}\n\n", 
-                i,
-                file.name,
-                file.typ,
-            ).as_bytes());
-            bytes.extend_from_slice(&file.data);
-            bytes.extend_from_slice("\n".as_bytes());
-        }
-        bytes.extend_from_slice("--boundary--\n".as_bytes());
+        let (reader, length) = multipart_body(body, files)?;
 
         let request = http
             .header("Content-Type", "multipart/form-data; boundary=boundary")
-            .body(bytes)
+            .body(AsyncBody::from_reader_sized(reader, length))
             .unwrap();
         isahc::send_async(request)
     } else if let Some(body) = body {
@@ -389,18 +567,23 @@ pub async fn create_response(
     } else {
         let request = http.body(()).unwrap();
         isahc::send_async(request)
-    }.await
+    }
+    .await
 }
 
 #[async_trait]
 impl Client for Bot {
-    async fn request_weak<T: DeserializeOwned>(
+    fn retry_deadline(&self) -> Option<Duration> {
+        self.retry_deadline
+    }
+
+    async fn request_with_headers<T: DeserializeOwned>(
         &self,
         method: Method,
         uri: &str,
         body: Option<&str>,
         files: &[Arc<File>],
-    ) -> Result<T> {
+    ) -> Result<(T, HeaderMap)> {
         let bucket = Bot::get_bucket(uri);
 
         // rate limits
@@ -426,7 +609,7 @@ impl Client for Bot {
                 }
             }
 
-            // sleep
+            // sleep (if dropped here, the lock is released and no request is counted)
             if !time.is_zero() {
                 tokio::time::sleep(time).await;
             }
@@ -442,10 +625,7 @@ impl Client for Bot {
         let http = isahc::Request::builder()
             .method(method)
             .uri(format!("https://discord.com/api/v10{}", uri))
-            .header(
-                "User-Agent",
-                format!("DiscordBot ({}, {})", "https://astavie.github.io/", VERSION),
-            )
+            .header("User-Agent", self.user_agent.as_str())
             .header("Authorization", format!("Bot {}", self.token));
 
         let mut response = create_response(http, body, files).await.map_err(|err| {
@@ -498,11 +678,22 @@ impl Client for Bot {
             return Err(RequestError::RateLimited);
         }
 
+        let headers = response.headers().clone();
         let string = response.text().await.unwrap();
         // println!("{}", string);
 
         if response.status().is_client_error() {
-            return Err(RequestError::ClientError(response.status()));
+            return Err(RequestError::ClientError(
+                response.status(),
+                serde_json::from_str(&string).ok(),
+            ));
+        }
+
+        if matches!(
+            response.status(),
+            StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT
+        ) {
+            return Err(RequestError::Unavailable);
         }
 
         if response.status().is_server_error() {
@@ -514,6 +705,7 @@ impl Client for Bot {
         } else {
             serde_json::from_str(&string)
         }
+        .map(|t| (t, headers))
         .map_err(|e| {
             println!("{}", e);
             RequestError::ServerError
@@ -1,5 +1,6 @@
 use std::{
     any::type_name,
+    cmp::Ordering,
     fmt,
     hash::{Hash, Hasher},
     marker::PhantomData,
@@ -35,6 +36,19 @@ impl<T> PartialEq for Snowflake<T> {
 
 impl<T> Eq for Snowflake<T> {}
 
+impl<T> PartialOrd for Snowflake<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Snowflake<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // ids are chronological
+        self.id.cmp(&other.id)
+    }
+}
+
 impl<T> Hash for Snowflake<T> {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.id.hash(state)
@@ -85,3 +99,25 @@ impl<T> fmt::Debug for Snowflake<T> {
         f.write_fmt(format_args!("<{}> {}", type_name::<T>(), self.id))
     }
 }
+
+// for fields where Discord expects a number instead of a string,
+// use with #[serde(with = "crate::resource::as_int")]
+pub mod as_int {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::Snowflake;
+
+    pub fn serialize<S, T>(id: &Snowflake<T>, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        s.serialize_u64(id.as_int())
+    }
+
+    pub fn deserialize<'de, D, T>(d: D) -> Result<Snowflake<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        u64::deserialize(d).map(Snowflake::new)
+    }
+}
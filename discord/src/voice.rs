@@ -0,0 +1,16 @@
+use serde::Deserialize;
+
+use super::{channel::Channel, guild::Guild, resource::Snowflake, user::User};
+
+#[derive(Debug, Deserialize)]
+pub struct VoiceState {
+    pub guild_id: Option<Snowflake<Guild>>,
+    pub channel_id: Option<Snowflake<Channel>>,
+    pub user_id: Snowflake<User>,
+    pub session_id: String,
+
+    pub deaf: bool,
+    pub mute: bool,
+    pub self_deaf: bool,
+    pub self_mute: bool,
+}
@@ -0,0 +1,50 @@
+use std::{
+    collections::HashMap,
+    env,
+    fs::{read_to_string, write},
+    path::PathBuf,
+    sync::{Mutex, OnceLock},
+};
+
+use discord::{guild::Guild, resource::Snowflake, user::User};
+
+type Wins = HashMap<Snowflake<Guild>, HashMap<Snowflake<User>, u32>>;
+
+static LEADERBOARD: OnceLock<Mutex<Wins>> = OnceLock::new();
+
+fn path() -> PathBuf {
+    env::var_os("LEADERBOARD_FILE")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| "leaderboard.json".into())
+}
+
+fn leaderboard() -> &'static Mutex<Wins> {
+    LEADERBOARD.get_or_init(|| {
+        let wins = read_to_string(path())
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default();
+        Mutex::new(wins)
+    })
+}
+
+pub fn record_win(guild: Snowflake<Guild>, user: Snowflake<User>) {
+    let mut wins = leaderboard().lock().unwrap();
+    *wins.entry(guild).or_default().entry(user).or_default() += 1;
+
+    let path = path();
+    if let Err(e) = write(&path, serde_json::to_string(&*wins).unwrap()) {
+        println!("could not save leaderboard {}: {}", path.display(), e);
+    }
+}
+
+pub fn top(guild: Snowflake<Guild>, count: usize) -> Vec<(Snowflake<User>, u32)> {
+    let wins = leaderboard().lock().unwrap();
+    let mut top: Vec<_> = wins
+        .get(&guild)
+        .map(|users| users.iter().map(|(&u, &w)| (u, w)).collect())
+        .unwrap_or_default();
+    top.sort_unstable_by(|a, b| b.1.cmp(&a.1));
+    top.truncate(count);
+    top
+}
@@ -1,27 +1,33 @@
+use std::env;
 use std::fmt::Write;
 use std::fmt::{self, Formatter};
 use std::marker::ConstParamTy;
 use std::ops::Index;
-use std::sync::Arc;
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
 use std::{fmt::Display, fs::read_to_string};
 use std::{matches, mem};
 
 use async_trait::async_trait;
 use discord::message::Field;
-use discord::{resource::Snowflake, user::User};
-use discord::{DiscordFormatter, DisplayDiscord};
-use rand::seq::IteratorRandom;
+use discord::{escape_string, DiscordFormatter, DisplayDiscord};
+use discord::{guild::Guild, resource::Snowflake, user::User};
+use rand::seq::SliceRandom;
 use rand::{thread_rng, Rng};
 use serde::Deserialize;
 
 use crate::enum_str;
+use crate::game::users;
 use crate::game::widget::Event;
-use crate::game::ActionResponse;
+use crate::game::{ActionResponse, ActionResult, GameError};
 use crate::game::{Game, GameMessage};
 
 use self::setup::Setup;
 
+#[cfg(feature = "leaderboard")]
+pub mod leaderboard;
 mod read;
+pub mod settings;
 mod setup;
 mod write;
 
@@ -63,6 +69,10 @@ impl CardData {
 pub struct PackData {
     black: Vec<CardData>,
     white: Vec<CardData>,
+
+    // theme color of the game while this pack is in play
+    #[serde(default)]
+    color: Option<u32>,
 }
 
 #[derive(ConstParamTy, PartialEq, Eq, Clone, Copy)]
@@ -125,7 +135,9 @@ impl<const C: CardType> Card<C> {
         white: &mut impl Iterator<Item = Option<Card<{ CardType::White }>>>,
         fmt: &mut DiscordFormatter<'_>,
     ) -> fmt::Result {
-        let mut prompt = self.text(packs);
+        // multi-line prompts (like haikus) may escape their newlines
+        let text = self.text(packs).replace("\\n", "\n");
+        let mut prompt = text.as_str();
 
         match C {
             CardType::White => {
@@ -135,41 +147,57 @@ impl<const C: CardType> Card<C> {
             CardType::Black => {}
         }
 
-        let inner_start = |fmt: &mut DiscordFormatter| match C {
+        let inner_start = |fmt: &mut DiscordFormatter, newline: bool| match C {
             CardType::White => fmt.end_code(),
+            CardType::Black if newline => Ok(()),
             CardType::Black => write!(fmt, " "),
         };
 
-        let inner_end = |fmt: &mut DiscordFormatter| match C {
+        let inner_end = |fmt: &mut DiscordFormatter, newline: bool| match C {
             CardType::White => fmt.start_code(),
+            CardType::Black if newline => Ok(()),
             CardType::Black => write!(fmt, " "),
         };
 
+        let is_space = |c: char| c.is_whitespace() && c != '\n';
+
         while let Some(pos) = prompt.find(['_', '{']) {
-            // trim spaces around blank
-            let before = prompt[..pos].trim_end();
+            // trim spaces around blank, but keep the lines intact
+            let before = prompt[..pos].trim_end_matches(is_space);
 
             let underscore = prompt.as_bytes()[pos] == b'_';
             let size = if underscore { 1 } else { 2 };
-            let after = prompt[pos + size..].trim_start();
+            let after = prompt[pos + size..].trim_start_matches(is_space);
+
+            let newline_before = before.ends_with('\n');
+            let newline_after = after.starts_with('\n');
 
             write!(fmt, "{}", before)?;
 
             if underscore {
                 match white.next() {
                     Some(Some(c)) => {
-                        inner_start(fmt)?;
+                        inner_start(fmt, newline_before)?;
                         c.fmt(packs, white, fmt)?;
-                        inner_end(fmt)?;
-                    }
-                    _ => {
-                        write!(fmt, "{}", &prompt[pos..pos + size])?;
+                        inner_end(fmt, newline_after)?;
                     }
+                    _ => match packs.1 {
+                        BlankStyle::Raw => write!(fmt, "{}", &prompt[pos..pos + size])?,
+                        BlankStyle::Line => {
+                            inner_start(fmt, newline_before)?;
+                            write!(fmt, "{}", BLANK_LINE)?;
+                            inner_end(fmt, newline_after)?;
+                        }
+                        BlankStyle::Hidden => {
+                            inner_start(fmt, newline_before)?;
+                            inner_end(fmt, true)?;
+                        }
+                    },
                 }
             } else {
-                inner_start(fmt)?;
+                inner_start(fmt, newline_before)?;
                 DisplayDiscord::fmt(&self.player, fmt)?;
-                inner_end(fmt)?;
+                inner_end(fmt, newline_after)?;
             }
 
             prompt = after;
@@ -196,7 +224,62 @@ impl<const C: CardType> Card<C> {
 }
 
 pub type Pack = Arc<(String, PackData)>;
-pub struct Packs(Vec<Pack>);
+
+fn cards_dir() -> PathBuf {
+    env::var_os("CARDS_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| "cards".into())
+}
+
+fn load_pack(name: &str, file: &str) -> Option<Pack> {
+    let path = cards_dir().join(file);
+    let data = match read_to_string(&path) {
+        Ok(data) => data,
+        Err(e) => {
+            println!("could not read pack {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    match serde_json::from_str(&data) {
+        Ok(pack) => Some(Arc::new((name.into(), pack))),
+        Err(e) => {
+            println!("could not parse pack {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+static PACKS: OnceLock<Vec<Pack>> = OnceLock::new();
+
+fn packs() -> &'static [Pack] {
+    // packs are only read from disk once and shared between games
+    PACKS.get_or_init(|| {
+        [
+            ("CAH Base", "base.json"),
+            ("EPPgroep.", "eppgroep.json"),
+            ("Modifiers", "modifiers.json"),
+        ]
+        .into_iter()
+        .filter_map(|(name, file)| load_pack(name, file))
+        .collect()
+    })
+}
+
+// how blanks that have not been filled in yet are shown
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum BlankStyle {
+    // as written on the card
+    #[default]
+    Raw,
+    // as a fixed width line
+    Line,
+    // not at all
+    Hidden,
+}
+
+const BLANK_LINE: &str = "▁▁▁▁▁";
+
+pub struct Packs(Vec<Pack>, BlankStyle);
 
 impl<const C: CardType> Index<Card<C>> for Packs {
     type Output = CardData;
@@ -218,7 +301,11 @@ pub enum PlayerKind {
 impl Display for PlayerKind {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
-            PlayerKind::User(u) => u.fmt(f),
+            // prefer names we have already seen over mentions
+            PlayerKind::User(u) => match users::display_name(*u) {
+                Some(name) => f.write_str(&escape_string(&name)),
+                None => u.fmt(f),
+            },
             PlayerKind::Rando(i) => write!(f, "``Rando Cardrissian #{}``", i + 1),
         }
     }
@@ -227,12 +314,15 @@ impl Display for PlayerKind {
 impl DisplayDiscord for PlayerKind {
     fn fmt(&self, f: &mut discord::DiscordFormatter<'_>) -> fmt::Result {
         match self {
-            PlayerKind::User(u) => {
-                f.unescaped().write_str("<@")?;
-                write!(f, "{}", u.as_int())?;
-                f.unescaped().write_str(">")?;
-                Ok(())
-            }
+            PlayerKind::User(u) => match users::display_name(*u) {
+                Some(name) => write!(f, "{}", name),
+                None => {
+                    f.unescaped().write_str("<@")?;
+                    write!(f, "{}", u.as_int())?;
+                    f.unescaped().write_str(">")?;
+                    Ok(())
+                }
+            },
             PlayerKind::Rando(i) => {
                 f.start_code()?;
                 write!(f, "Rando Cardrissian #{}", i + 1)?;
@@ -243,11 +333,13 @@ impl DisplayDiscord for PlayerKind {
     }
 }
 
+#[derive(Clone)]
 pub struct Player {
     pub kind: PlayerKind,
     pub points: i32,
     pub hand: Vec<Card<{ CardType::White }>>,
     pub selected: Vec<Option<usize>>,
+    pub submitted: bool,
 }
 
 impl Player {
@@ -257,6 +349,7 @@ impl Player {
             points: 0,
             hand: Vec::new(),
             selected: Vec::new(),
+            submitted: false,
         }
     }
     pub fn selected(&self) -> impl Iterator<Item = Option<Card<{ CardType::White }>>> + '_ {
@@ -267,25 +360,47 @@ impl Player {
     }
 }
 
-pub fn draw(
-    players: &mut [Player],
-    num: usize,
-    packs: &mut Packs,
-    max: usize,
+// four rows of five cards, leaving the last row for the submit button
+pub const MAX_HAND: usize = 20;
+
+pub fn hand_size(
+    cards: usize,
+    packing_heat: bool,
+    packs: &Packs,
     prompt: Card<{ CardType::Black }>,
-) -> bool {
-    let player = &mut players[num];
+) -> usize {
+    let size = if packing_heat {
+        // an extra card for every blank after the first
+        cards + packs[prompt].blanks_black() - 1
+    } else {
+        cards
+    };
+    size.min(MAX_HAND)
+}
 
-    // remove selected cards
+pub fn discard_selected(player: &mut Player) {
     let mut selected = std::mem::replace(&mut player.selected, Vec::new());
+    player.submitted = false;
     selected.sort_unstable_by(|a, b| b.cmp(a));
     for index in selected {
         if let Some(index) = index {
             player.hand.remove(index);
         }
     }
-    // draw new cards
-    for _ in 0..max - player.hand.len() {
+}
+
+pub fn draw(
+    players: &mut [Player],
+    num: usize,
+    packs: &mut Packs,
+    max: usize,
+    prompt: Card<{ CardType::Black }>,
+) -> bool {
+    let player = &mut players[num];
+    discard_selected(player);
+
+    // extra cards left over from packing heat are kept until the player discards them
+    for _ in 0..max.saturating_sub(player.hand.len()) {
         let draw_white = packs.draw_white(players);
         let player = &mut players[num];
         player.hand.push(match draw_white {
@@ -293,23 +408,40 @@ pub fn draw(
             None => return false,
         });
     }
-    // if rando, give answer immediately
+    // if rando, discard and give answer immediately
     let player = &mut players[num];
     if matches!(player.kind, PlayerKind::Rando(_)) {
-        fn choose(raw: &mut Vec<usize>) -> Option<usize> {
-            let i = (0..raw.len()).choose(&mut thread_rng())?;
-            Some(raw.swap_remove(i))
+        player.hand.shuffle(&mut thread_rng());
+        player.hand.truncate(max);
+        return answer_random(player, packs, prompt);
+    }
+    true
+}
+
+const RANDO_ATTEMPTS: usize = 8;
+
+// picks random cards from the hand like a player would, false if the prompt could not be filled
+fn answer_random(player: &mut Player, packs: &Packs, prompt: Card<{ CardType::Black }>) -> bool {
+    // cards with blanks of their own may use up the hand, so try a few orders
+    for _ in 0..RANDO_ATTEMPTS {
+        let mut indices: Vec<_> = (0..player.hand.len()).collect();
+        indices.shuffle(&mut thread_rng());
+
+        player.selected.clear();
+        for i in indices {
+            if prompt.is_filled(packs, player.selected()) {
+                break;
+            }
+            player.selected.push(Some(i));
         }
 
-        let mut indices: Vec<_> = (0..max).collect();
-        while !prompt.is_filled(packs, player.selected()) {
-            player.selected.push(Some(match choose(&mut indices) {
-                Some(i) => i,
-                None => return false,
-            }))
+        if prompt.is_filled(packs, player.selected()) {
+            player.submitted = true;
+            return true;
         }
     }
-    true
+    player.selected.clear();
+    false
 }
 
 impl Packs {
@@ -389,17 +521,59 @@ pub enum CAH {
     Read(Ingame),
 }
 
-enum_str!(Action: Start, ShowHand, ChangeHand, Continue, Done);
+enum_str!(Action: Start, ShowHand, ChangeHand, Continue, Winner, Done);
 enum_str!(Panel: Main, Hand);
 
 pub struct Ingame {
+    // only needed to record wins on the leaderboard
+    #[cfg(feature = "leaderboard")]
+    pub guild: Option<Snowflake<Guild>>,
     pub packs: Packs,
     pub cards: usize,
     pub points: i32,
+    pub packing_heat: bool,
+    pub rando_czar: bool,
     pub players: Vec<Player>,
 
     pub prompt: Card<{ CardType::Black }>,
     pub czar: PlayerKind,
+
+    // announced to the channel once the czar has picked
+    pub round_winner: Option<String>,
+}
+
+impl Ingame {
+    // whether everyone but the czar has submitted an answer
+    fn is_ready_to_judge(&self) -> bool {
+        self.players
+            .iter()
+            .all(|p| p.kind == self.czar || p.submitted)
+    }
+
+    // moves the game out, leaving an empty game behind to be replaced
+    fn take(&mut self) -> Ingame {
+        let nobody = PlayerKind::Rando(0);
+        mem::replace(
+            self,
+            Ingame {
+                #[cfg(feature = "leaderboard")]
+                guild: None,
+                packs: Packs(Vec::new(), BlankStyle::Raw),
+                cards: 0,
+                points: 0,
+                packing_heat: false,
+                rando_czar: false,
+                players: Vec::new(),
+                prompt: Card {
+                    pack: 0,
+                    card: 0,
+                    player: nobody,
+                },
+                czar: nobody,
+                round_winner: None,
+            },
+        )
+    }
 }
 
 #[async_trait]
@@ -408,7 +582,20 @@ impl Game for CAH {
     type Panel = Panel;
 
     const NAME: &'static str = "Crappy Ableist Humor";
-    const COLOR: u32 = 0x000000;
+    // pure black is shown as no color at all
+    const COLOR: u32 = 0x010101;
+
+    fn color(&self) -> u32 {
+        match self {
+            CAH::Setup(_) => Self::COLOR,
+            CAH::Write(i) | CAH::Read(i) => i
+                .packs
+                .0
+                .iter()
+                .find_map(|p| p.1.color)
+                .unwrap_or(Self::COLOR),
+        }
+    }
 
     fn create_panel(
         &mut self,
@@ -424,15 +611,15 @@ impl Game for CAH {
         }
     }
 
-    fn on_action(&mut self, action: Action, _panel: Panel, _user: &User) -> ActionResponse<Panel> {
+    fn on_action(&mut self, action: Action, _panel: Panel, _user: &User) -> ActionResult<Panel> {
         if action == Action::Done {
-            return ActionResponse::Exit;
+            return Ok(ActionResponse::Exit);
         }
 
         match self {
             CAH::Setup(s) => {
                 if action != Action::Start {
-                    return ActionResponse::None;
+                    return Ok(ActionResponse::None);
                 }
 
                 let players: Vec<_> = s.players().collect();
@@ -441,67 +628,82 @@ impl Game for CAH {
                         .0
                         .iter()
                         .enumerate()
-                        .filter(|(i, _)| s.selected_packs.contains(i))
+                        .filter(|(i, _)| s.selected_packs().contains(i))
                         .map(|(_, p)| p)
                         .cloned()
                         .collect(),
+                    s.blank_style(),
                 );
 
                 if players.len() < 2 {
-                    return ActionResponse::Error(GameMessage::new(
+                    return Ok(ActionResponse::Error(GameMessage::new(
                         vec![Field::new("Error", "not enough players")],
                         vec![],
-                    ));
+                    )));
                 }
 
-                let czar = match players.iter().find(|p| matches!(p, PlayerKind::User(_))) {
-                    Some(p) => p.clone(),
-                    None => {
-                        return ActionResponse::Error(GameMessage::new(
-                            vec![Field::new(
-                                "Error",
-                                "I know you want to witness the AI uprising, but you can't play a game with only Rando Cardrissian"
-                            )],
-                            vec![],
-                        ));
-                    }
-                };
+                if !players.iter().any(|p| matches!(p, PlayerKind::User(_))) {
+                    return Ok(ActionResponse::Error(GameMessage::new(
+                        vec![Field::new(
+                            "Error",
+                            "I know you want to witness the AI uprising, but you can't play a game with only Rando Cardrissian"
+                        )],
+                        vec![],
+                    )));
+                }
+
+                // the same players that take turns as czar later on
+                let rando_czar = s.rando_czar();
+                let czar = *players
+                    .iter()
+                    .find(|p| rando_czar || matches!(p, PlayerKind::User(_)))
+                    .unwrap();
 
                 let mut players = players.into_iter().map(Player::new).collect::<Vec<_>>();
 
                 let prompt = match packs.draw_black(&players) {
                     Some(c) => c,
                     None => {
-                        return ActionResponse::Error(GameMessage::new(
+                        return Ok(ActionResponse::Error(GameMessage::new(
                             vec![Field::new(
                                 "Error",
                                 "selected packs do not have any black cards",
                             )],
                             vec![],
-                        ));
+                        )));
                     }
                 };
 
+                let max = hand_size(s.cards(), s.packing_heat(), &packs, prompt);
                 for num in 0..players.len() {
-                    if !draw(&mut players, num, &mut packs, s.cards as usize, prompt) {
-                        return ActionResponse::Error(GameMessage::new(
+                    // the czar does not play this round
+                    if players[num].kind == czar {
+                        continue;
+                    }
+                    if !draw(&mut players, num, &mut packs, max, prompt) {
+                        return Ok(ActionResponse::Error(GameMessage::new(
                             vec![Field::new(
                                 "Error",
                                 "selected packs do not have enough white cards to start",
                             )],
                             vec![],
-                        ));
+                        )));
                     }
                 }
 
                 // start game!
                 let ingame = Ingame {
+                    #[cfg(feature = "leaderboard")]
+                    guild: s.guild,
                     packs,
-                    cards: s.cards as usize,
-                    points: s.points,
+                    cards: s.cards(),
+                    points: s.points(),
+                    packing_heat: s.packing_heat(),
+                    rando_czar,
                     players,
                     prompt,
                     czar,
+                    round_winner: None,
                 };
 
                 if ingame
@@ -517,131 +719,149 @@ impl Game for CAH {
                     *self = CAH::Write(ingame);
                 }
 
-                ActionResponse::NextMain(true)
+                Ok(ActionResponse::NextMain(true))
             }
             CAH::Write(i) => match action {
-                Action::ShowHand => ActionResponse::Reply(Panel::Hand),
+                Action::ShowHand => Ok(ActionResponse::Reply(Panel::Hand)),
                 Action::ChangeHand => {
-                    if i.players
-                        .iter()
-                        .all(|p| i.czar == p.kind || i.prompt.is_filled(&i.packs, p.selected()))
-                    {
-                        *self = CAH::Read(unsafe {
-                            mem::replace(
-                                i,
-                                Ingame {
-                                    packs: Packs(Vec::new()),
-                                    cards: 0,
-                                    points: 0,
-                                    players: Vec::new(),
-                                    prompt: mem::zeroed(),
-                                    czar: mem::zeroed(),
-                                },
-                            )
-                        });
-                        ActionResponse::NextMain(false)
+                    if i.is_ready_to_judge() {
+                        *self = CAH::Read(i.take());
+                        Ok(ActionResponse::NextMain(false))
                     } else {
-                        ActionResponse::EditMain
+                        Ok(ActionResponse::EditMain)
                     }
                 }
-                _ => ActionResponse::None,
+                _ => Ok(ActionResponse::None),
             },
             CAH::Read(i) => {
+                if action == Action::Winner {
+                    return Ok(match i.round_winner.take() {
+                        Some(winner) => ActionResponse::Announce(GameMessage::new(
+                            vec![Field::new("Round Winner", winner)],
+                            vec![],
+                        )),
+                        None => ActionResponse::None,
+                    });
+                }
                 if action != Action::Continue {
-                    return ActionResponse::None;
+                    return Ok(ActionResponse::None);
                 }
 
-                // new prompt
-                i.prompt = match i.packs.draw_black(&i.players) {
+                // the next round is prepared on the side, so an error leaves this one as it was
+                let prompt = match i.packs.draw_black(&i.players) {
                     Some(c) => c,
-                    None => todo!("no black cards"),
+                    None => return Err(GameError::new("there are no black cards left")),
                 };
 
-                // draw cards
-                for num in 0..i.players.len() {
-                    if !draw(&mut i.players, num, &mut i.packs, i.cards, i.prompt) {
-                        todo!("no white cards");
-                    }
-                }
-
                 // new czar
-                let czar = i
+                let czar = *i
                     .players
                     .iter()
                     .map(|p| &p.kind)
-                    .filter(|p| matches!(p, PlayerKind::User(_)))
+                    .filter(|p| i.rando_czar || matches!(p, PlayerKind::User(_)))
                     .cycle()
                     .skip_while(|p| **p != i.czar)
                     .skip(1)
                     .next()
                     .unwrap();
 
-                if *czar != i.czar {
-                    i.czar = czar.clone();
-                    *self = CAH::Write(unsafe {
-                        mem::replace(
-                            i,
-                            Ingame {
-                                packs: Packs(Vec::new()),
-                                cards: 0,
-                                points: 0,
-                                players: Vec::new(),
-                                prompt: mem::zeroed(),
-                                czar: mem::zeroed(),
-                            },
-                        )
-                    });
+                // draw cards
+                let max = hand_size(i.cards, i.packing_heat, &i.packs, prompt);
+                let mut players = i.players.clone();
+                for num in 0..players.len() {
+                    // the czar keeps their hand for later rounds, but is not dealt new cards
+                    if players[num].kind == czar {
+                        discard_selected(&mut players[num]);
+                        continue;
+                    }
+                    if !draw(&mut players, num, &mut i.packs, max, prompt) {
+                        return Err(GameError::new("there are not enough white cards left"));
+                    }
+                }
+
+                i.prompt = prompt;
+                i.players = players;
+                i.czar = czar;
+
+                // randos answer immediately, so the new round may already be ready to judge
+                if !i.is_ready_to_judge() {
+                    *self = CAH::Write(i.take());
                 }
 
-                ActionResponse::NextMain(true)
+                Ok(ActionResponse::NextMain(true))
             }
         }
     }
 
-    fn new(user: User) -> Self {
-        CAH::Setup(Setup {
-            packs: Packs(vec![
-                Arc::new((
-                    "CAH Base".into(),
-                    serde_json::from_str(read_to_string("cards/base.json").unwrap().as_str())
-                        .unwrap(),
-                )),
-                Arc::new((
-                    "EPPgroep.".into(),
-                    serde_json::from_str(read_to_string("cards/eppgroep.json").unwrap().as_str())
-                        .unwrap(),
-                )),
-                Arc::new((
-                    "EPPgroep.".into(),
-                    serde_json::from_str(read_to_string("cards/eppgroep.json").unwrap().as_str())
-                        .unwrap(),
-                )),
-                Arc::new((
-                    "Modifiers".into(),
-                    serde_json::from_str(read_to_string("cards/modifiers.json").unwrap().as_str())
-                        .unwrap(),
-                )),
-                Arc::new((
-                    "Modifiers".into(),
-                    serde_json::from_str(read_to_string("cards/modifiers.json").unwrap().as_str())
-                        .unwrap(),
-                )),
-                Arc::new((
-                    "Modifiers".into(),
-                    serde_json::from_str(read_to_string("cards/modifiers.json").unwrap().as_str())
-                        .unwrap(),
-                )),
-                Arc::new((
-                    "Modifiers".into(),
-                    serde_json::from_str(read_to_string("cards/modifiers.json").unwrap().as_str())
-                        .unwrap(),
-                )),
-            ]),
-            selected_packs: vec![0],
-            bots: 0,
-            cards: 10,
-            points: 8,
-            users: vec![user.id],
-        })
+    fn rules() -> GameMessage {
+        GameMessage::new(
+            vec![
+                Field::new(
+                    "Rounds",
+                    "Every round one player is the Card Czar, who reads the prompt. \
+                    Everyone else picks cards from their hand to fill in its blanks \
+                    and submits their answer.",
+                ),
+                Field::new(
+                    "Judging",
+                    "Once everyone has submitted, the answers are shown anonymously \
+                    and the Card Czar picks their favorite. \
+                    The player behind that answer gets a point. \
+                    Members that can manage messages may pick in place of the Card Czar.",
+                ),
+                Field::new(
+                    "Czar",
+                    "After each round, the next player becomes the Card Czar. \
+                    Rando Cardrissian plays random cards, \
+                    and only judges when Rando Czar is enabled.",
+                ),
+                Field::new(
+                    "Winning",
+                    "The first player to reach the point goal wins the game.",
+                ),
+                Field::new(
+                    "Packing Heat",
+                    "When enabled, prompts with multiple blanks deal an extra card \
+                    for every blank after the first. \
+                    Players holding more cards than the round allows \
+                    have to discard the extra cards before submitting.",
+                ),
+                Field::new(
+                    "Rando Czar",
+                    "When enabled, Rando Cardrissian also takes turns as the Card Czar, \
+                    picking a random answer once anyone asks it to.",
+                ),
+                Field::new(
+                    "Blanks",
+                    "Blanks that have not been filled in yet are shown as written on the card, \
+                    as lines, or hidden entirely.",
+                ),
+            ],
+            vec![],
+        )
+    }
+
+    fn new(user: User, guild: Option<Snowflake<Guild>>) -> Self {
+        let settings = guild.map(settings::get).unwrap_or_default();
+        let packs = packs();
+
+        let selected_packs = match settings.packs {
+            Some(names) => packs
+                .iter()
+                .enumerate()
+                .filter(|(_, p)| names.contains(&p.0))
+                .map(|(i, _)| i)
+                .collect(),
+            None => vec![0],
+        };
+
+        CAH::Setup(Setup::new(
+            guild,
+            Packs(packs.to_vec(), BlankStyle::Raw),
+            selected_packs,
+            settings.cards.unwrap_or(10),
+            settings.points.unwrap_or(8),
+            user.id,
+        ))
     }
 }
@@ -1,9 +1,21 @@
-use discord::message::{ActionRow, ActionRowComponent, Button, ButtonStyle, Field};
-use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use discord::{
+    escape_string,
+    guild::Member,
+    message::{ActionRow, ActionRowComponent, Button, ButtonStyle, Field, FIELD_VALUE_LIMIT},
+    permissions::Permission,
+    truncate_escaped,
+};
+use rand::{rngs::StdRng, seq::SliceRandom, thread_rng, Rng, SeedableRng};
 
-use crate::game::{widget::Event, GameMessage, B64_TABLE};
+use crate::game::{
+    custom_id::{CustomId, GRID},
+    widget::{decode_index, encode_index, Event},
+    Game, GameMessage,
+};
 
-use super::{Action, Ingame, Player, PlayerKind};
+use super::{Action, Ingame, Player, PlayerKind, CAH};
+
+const RANDO_PICK_ID: CustomId<'static> = CustomId::new(GRID, "rando");
 
 impl Ingame {
     pub fn random_indices(&self) -> Vec<&Player> {
@@ -19,22 +31,39 @@ impl Ingame {
     }
     pub fn create_read(&mut self, msg: &mut GameMessage, event: &Event) -> Option<Action> {
         if let PlayerKind::User(user) = self.czar {
-            if let Some(i) = event.matches(|i| {
-                if i.user.id != user {
+            if let Some((i, czar)) = event.matches(|i| {
+                // moderators may pick in place of a czar that is not responding
+                let is_moderator = i
+                    .member
+                    .as_ref()
+                    .and_then(|m| m.permissions)
+                    .is_some_and(|p| p.contains(Permission::ManageMessages));
+
+                if i.user.id != user && !is_moderator {
                     None
                 } else {
-                    let s = i.data.custom_id.strip_prefix('#')?;
-                    let c = s.chars().next()?;
-                    B64_TABLE
-                        .iter()
-                        .position(|&p| p == c)
-                        .filter(|&i| i < self.players.len() - 1)
+                    let id = CustomId::parse(&i.data.custom_id).filter(|id| id.is(GRID, "pick"))?;
+                    let index = decode_index(id.arg?).filter(|&i| i < self.players.len() - 1)?;
+
+                    // prefer the nickname of the czar within the guild
+                    let name = i
+                        .member
+                        .as_ref()
+                        .and_then(Member::display_name)
+                        .unwrap_or(i.user.username.as_str());
+                    Some((index, escape_string(name)))
                 }
             }) {
-                return self.create_winner(msg, i);
+                return self.create_winner(msg, i, czar);
             }
         }
 
+        // a rando czar picks a random answer once anyone asks it to
+        if matches!(self.czar, PlayerKind::Rando(_)) && event.custom_id() == Some(RANDO_PICK_ID) {
+            let i = thread_rng().gen_range(0..self.players.len() - 1);
+            return self.create_winner(msg, i, self.czar.to_string());
+        }
+
         msg.fields.push(Field::new(
             "Players",
             self.players
@@ -42,7 +71,11 @@ impl Ingame {
                 .map(|p| {
                     format!(
                         "{} `{:2}` {}",
-                        if p.kind == self.czar { "👑" } else { "✅" },
+                        if p.kind == self.czar {
+                            CAH::CZAR_EMOJI
+                        } else {
+                            CAH::DONE_EMOJI
+                        },
                         p.points,
                         p.kind,
                     )
@@ -51,22 +84,34 @@ impl Ingame {
                 .join("\n"),
         ));
 
+        let answers = self
+            .random_indices()
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                format!(
+                    "{}. {}",
+                    i + 1,
+                    self.prompt.fill(&self.packs, &mut p.selected())
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
         msg.fields.push(Field::new(
             "Answers",
-            self.random_indices()
-                .iter()
-                .enumerate()
-                .map(|(i, p)| {
-                    format!(
-                        "{}. {}",
-                        i + 1,
-                        self.prompt.fill(&self.packs, &mut p.selected())
-                    )
-                })
-                .collect::<Vec<_>>()
-                .join("\n"),
+            truncate_escaped(&answers, FIELD_VALUE_LIMIT),
         ));
 
+        if matches!(self.czar, PlayerKind::Rando(_)) {
+            msg.append_component(ActionRowComponent::Button(Button::Action {
+                style: ButtonStyle::Primary,
+                custom_id: RANDO_PICK_ID.to_string(),
+                label: Some("Let Rando Pick".into()),
+                disabled: false,
+            }));
+            return None;
+        }
+
         // picker
         let mut iter = 0..self.players.len() - 1;
         loop {
@@ -76,7 +121,9 @@ impl Ingame {
                     Some(i) => {
                         buttons.push(ActionRowComponent::Button(Button::Action {
                             style: ButtonStyle::Primary,
-                            custom_id: format!("#{}", B64_TABLE[i]),
+                            custom_id: CustomId::new(GRID, "pick")
+                                .arg(&encode_index(i).to_string())
+                                .to_string(),
                             label: Some((i + 1).to_string()),
                             disabled: false,
                         }));
@@ -92,7 +139,7 @@ impl Ingame {
             msg.components.push(ActionRow::new(buttons));
         }
     }
-    fn create_winner(&mut self, msg: &mut GameMessage, i: usize) -> Option<Action> {
+    fn create_winner(&mut self, msg: &mut GameMessage, i: usize, czar: String) -> Option<Action> {
         let mut indices: Vec<_> = self
             .players
             .iter_mut()
@@ -105,6 +152,8 @@ impl Ingame {
         let winner = &mut *indices[i];
         winner.points += 1;
         let total_points = winner.points;
+        #[cfg(feature = "leaderboard")]
+        let winner_kind = winner.kind;
 
         let name = winner.kind.to_string();
         let answer = self.prompt.fill(&self.packs, &mut winner.selected());
@@ -116,23 +165,59 @@ impl Ingame {
             .collect::<Vec<_>>()
             .join("\n");
 
+        let answers = self
+            .random_indices()
+            .iter()
+            .enumerate()
+            .map(|(n, p)| {
+                format!(
+                    "{}{}. {} ({})",
+                    if n == i { "🏆 " } else { "" },
+                    n + 1,
+                    self.prompt.fill(&self.packs, &mut p.selected()),
+                    p.kind,
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
         return if total_points >= self.points {
+            #[cfg(feature = "leaderboard")]
+            if let (Some(guild), PlayerKind::User(user)) = (self.guild, winner_kind) {
+                super::leaderboard::record_win(guild, user);
+            }
+
             msg.fields.extend(vec![
                 Field::new("Players", points),
                 Field::new(
                     "We have a winner!",
                     format!("{} won the game with `{}` points!", name, total_points),
                 ),
-                Field::new("Last words", format!(">>> {}", answer)),
+                Field::new(
+                    "Last words",
+                    truncate_escaped(&format!(">>> {}", answer), FIELD_VALUE_LIMIT),
+                ),
+                Field::new("Answers", truncate_escaped(&answers, FIELD_VALUE_LIMIT)),
             ]);
             Some(Action::Done)
         } else {
             msg.fields.extend(vec![
                 Field::new("Players", points),
-                Field::new("Round Winner", format!("{}\n\n>>> {}", name, answer)),
+                Field::new(
+                    "Round Winner",
+                    truncate_escaped(
+                        &format!("{}\n*picked by {}*\n\n>>> {}", name, czar, answer),
+                        FIELD_VALUE_LIMIT,
+                    ),
+                ),
+                Field::new("Answers", truncate_escaped(&answers, FIELD_VALUE_LIMIT)),
             ]);
             msg.append_action(Action::Continue, ButtonStyle::Primary, "Continue".into());
-            None
+            self.round_winner = Some(truncate_escaped(
+                &format!("{}\n\n>>> {}", name, answer),
+                FIELD_VALUE_LIMIT,
+            ));
+            Some(Action::Winner)
         };
     }
 }
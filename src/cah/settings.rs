@@ -0,0 +1,71 @@
+use std::{
+    collections::HashMap,
+    env,
+    fs::{read_to_string, write},
+    path::PathBuf,
+    sync::{Mutex, OnceLock},
+};
+
+use discord::{guild::Guild, resource::Snowflake};
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Clone, Serialize, Deserialize)]
+pub struct GuildSettings {
+    #[serde(default)]
+    pub packs: Option<Vec<String>>,
+    #[serde(default)]
+    pub cards: Option<i32>,
+    #[serde(default)]
+    pub points: Option<i32>,
+}
+
+impl GuildSettings {
+    fn merge(&mut self, other: GuildSettings) {
+        if other.packs.is_some() {
+            self.packs = other.packs;
+        }
+        if other.cards.is_some() {
+            self.cards = other.cards;
+        }
+        if other.points.is_some() {
+            self.points = other.points;
+        }
+    }
+}
+
+static SETTINGS: OnceLock<Mutex<HashMap<Snowflake<Guild>, GuildSettings>>> = OnceLock::new();
+
+fn path() -> PathBuf {
+    env::var_os("SETTINGS_FILE")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| "settings.json".into())
+}
+
+fn settings() -> &'static Mutex<HashMap<Snowflake<Guild>, GuildSettings>> {
+    SETTINGS.get_or_init(|| {
+        let settings = read_to_string(path())
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default();
+        Mutex::new(settings)
+    })
+}
+
+pub fn get(guild: Snowflake<Guild>) -> GuildSettings {
+    settings()
+        .lock()
+        .unwrap()
+        .get(&guild)
+        .cloned()
+        .unwrap_or_default()
+}
+
+pub fn set(guild: Snowflake<Guild>, new: GuildSettings) {
+    let mut settings = settings().lock().unwrap();
+    settings.entry(guild).or_default().merge(new);
+
+    let path = path();
+    if let Err(e) = write(&path, serde_json::to_string(&*settings).unwrap()) {
+        println!("could not save settings {}: {}", path.display(), e);
+    }
+}
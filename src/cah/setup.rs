@@ -1,49 +1,116 @@
-use crate::game::{widget::Event, GameMessage};
+use crate::game::{
+    settings::{ChoiceKey, FlagKey, NumberKey, PlayersKey, SelectKey, SettingsForm},
+    widget::Event,
+    GameMessage,
+};
 
 use discord::{
+    guild::Guild,
     message::{ButtonStyle, Field},
     resource::Snowflake,
     user::User,
 };
 
-use super::{Action, Packs, PlayerKind};
+use super::{Action, BlankStyle, Packs, PlayerKind, MAX_HAND};
 
 pub struct Setup {
+    #[cfg(feature = "leaderboard")]
+    pub guild: Option<Snowflake<Guild>>,
     pub packs: Packs,
-    pub selected_packs: Vec<usize>,
+    pub form: SettingsForm,
 
-    pub bots: i32,
-    pub cards: i32,
-    pub points: i32,
-    pub users: Vec<Snowflake<User>>,
+    selected_packs: SelectKey,
+    bots: NumberKey,
+    cards: NumberKey,
+    points: NumberKey,
+    users: PlayersKey,
+    packing_heat: FlagKey,
+    blanks: ChoiceKey,
+    rando_czar: FlagKey,
 }
 
 impl Setup {
-    pub fn players(&self) -> impl Iterator<Item = PlayerKind> + '_ {
-        let bots = (0..self.bots).map(|i| PlayerKind::Rando(i as usize));
-        let users = self.users.iter().map(|&u| PlayerKind::User(u));
-        Iterator::chain(users, bots)
-    }
-    pub fn create(&mut self, msg: &mut GameMessage, event: &Event) -> Option<Action> {
-        // pack selection
-        msg.create_select(
-            event,
-            "Packs".into(),
-            self.packs.0.iter().map(|p| p.0.clone()),
-            &mut self.selected_packs,
+    // the guild is only kept for the leaderboard
+    #[cfg_attr(not(feature = "leaderboard"), allow(unused_variables))]
+    pub fn new(
+        guild: Option<Snowflake<Guild>>,
+        packs: Packs,
+        selected_packs: Vec<usize>,
+        cards: i32,
+        points: i32,
+        user: Snowflake<User>,
+    ) -> Self {
+        let mut form = SettingsForm::default();
+        let selected_packs = form.multi_select(
+            "Packs",
+            packs.0.iter().map(|p| p.0.clone()).collect(),
+            selected_packs,
         );
+        let bots = form.number("Bots", 0, 0, i32::MAX, 1);
+        let cards = form.number("Cards", cards, 5, MAX_HAND as i32, 1);
+        let points = form.number("Points", points, 1, i32::MAX, 1);
+        let users = form.players("Players", vec![user]);
+        let packing_heat = form.flag("Packing Heat", false);
+        let blanks = form.choice(
+            "Blanks",
+            vec!["Raw".into(), "Lines".into(), "Hidden".into()],
+            0,
+        );
+        let rando_czar = form.flag("Rando Czar", false);
 
-        // bots
-        msg.create_number(event, "Bots".into(), &mut self.bots, 0, i32::MAX);
-
-        // cards
-        msg.create_number(event, "Cards".into(), &mut self.cards, 5, 25);
+        Self {
+            #[cfg(feature = "leaderboard")]
+            guild,
+            packs,
+            form,
+            selected_packs,
+            bots,
+            cards,
+            points,
+            users,
+            packing_heat,
+            blanks,
+            rando_czar,
+        }
+    }
 
-        // points
-        msg.create_number(event, "Points".into(), &mut self.points, 1, i32::MAX);
+    pub fn selected_packs(&self) -> &[usize] {
+        self.form.get_selected(self.selected_packs)
+    }
+    pub fn bots(&self) -> usize {
+        self.form.get_number(self.bots) as usize
+    }
+    pub fn cards(&self) -> usize {
+        self.form.get_number(self.cards) as usize
+    }
+    pub fn points(&self) -> i32 {
+        self.form.get_number(self.points)
+    }
+    pub fn users(&self) -> &[Snowflake<User>] {
+        self.form.get_players(self.users)
+    }
+    pub fn packing_heat(&self) -> bool {
+        self.form.get_flag(self.packing_heat)
+    }
+    pub fn rando_czar(&self) -> bool {
+        self.form.get_flag(self.rando_czar)
+    }
+    pub fn blank_style(&self) -> BlankStyle {
+        match self.form.get_choice(self.blanks) {
+            0 => BlankStyle::Raw,
+            1 => BlankStyle::Line,
+            _ => BlankStyle::Hidden,
+        }
+    }
 
-        // players
-        msg.create_join(event, &mut self.users);
+    pub fn players(&self) -> impl Iterator<Item = PlayerKind> + '_ {
+        let bots = (0..self.bots()).map(PlayerKind::Rando);
+        let users = self.users().iter().map(|&u| PlayerKind::User(u));
+        Iterator::chain(users, bots)
+    }
+    pub fn create(&mut self, msg: &mut GameMessage, event: &Event) -> Option<Action> {
+        // packs, bots, cards, points, players and house rules
+        self.form.render(msg, event);
 
         let mut players_str = self
             .players()
@@ -59,6 +126,7 @@ impl Setup {
 
         // start button
         msg.append_action(Action::Start, ButtonStyle::Primary, "Start".into());
+        msg.append_rules();
 
         None
     }
@@ -1,13 +1,17 @@
 use discord::{
     escape_string,
-    message::{ButtonStyle, Field},
+    message::{ActionRowComponent, Button, ButtonStyle, Field, FIELD_VALUE_LIMIT},
     resource::Snowflake,
+    truncate_escaped,
     user::User,
 };
 
-use crate::game::{widget::Event, GameMessage};
+use crate::game::{custom_id::CustomId, widget::Event, Game, GameMessage};
 
-use super::{Action, Ingame, Panel, PlayerKind};
+use super::{discard_selected, hand_size, Action, Ingame, Panel, PlayerKind, CAH};
+
+const SUBMIT_ID: CustomId<'static> = CustomId::new("hand", "submit");
+const DISCARD_ID: CustomId<'static> = CustomId::new("hand", "discard");
 
 impl Ingame {
     pub fn create_write(
@@ -27,11 +31,11 @@ impl Ingame {
                             format!(
                                 "{} `{:2}` {}",
                                 if p.kind == self.czar {
-                                    "👑"
-                                } else if self.prompt.is_filled(&self.packs, p.selected()) {
-                                    "✅"
+                                    CAH::CZAR_EMOJI
+                                } else if p.submitted {
+                                    CAH::DONE_EMOJI
                                 } else {
-                                    "💭"
+                                    CAH::WAITING_EMOJI
                                 },
                                 p.points,
                                 p.kind,
@@ -47,9 +51,11 @@ impl Ingame {
                 ));
 
                 msg.append_action(Action::ShowHand, ButtonStyle::Primary, "Show Hand".into());
+                msg.append_rules();
                 None
             }
             Panel::Hand => {
+                let max = hand_size(self.cards, self.packing_heat, &self.packs, self.prompt);
                 let player = self
                     .players
                     .iter_mut()
@@ -57,9 +63,17 @@ impl Ingame {
 
                 let mut changed = false;
                 if self.czar != PlayerKind::User(user) {
-                    changed = msg.create_select_grid(
+                    // cards above the hand size left over from packing heat go first
+                    let extra = player.hand.len().saturating_sub(max);
+                    let discarding = player.selected.iter().flatten().count();
+                    if (1..=extra).contains(&discarding) && event.custom_id() == Some(DISCARD_ID) {
+                        discard_selected(player);
+                    }
+                    let extra = player.hand.len().saturating_sub(max);
+
+                    let done_changed = msg.create_select_grid(
                         event,
-                        self.cards,
+                        player.hand.len(),
                         &mut player.selected,
                         |selected| {
                             self.prompt.is_filled(
@@ -69,28 +83,87 @@ impl Ingame {
                         },
                     );
 
+                    // changing a submitted answer takes it back
+                    if done_changed && player.submitted {
+                        player.submitted = false;
+                        changed = true;
+                    }
+
+                    // only submitting locks in the answer
+                    let filled = self.prompt.is_filled(&self.packs, player.selected());
+                    if filled
+                        && extra == 0
+                        && !player.submitted
+                        && event.custom_id() == Some(SUBMIT_ID)
+                    {
+                        player.submitted = true;
+                        changed = true;
+                    }
+
+                    msg.append_component(ActionRowComponent::Button(Button::Action {
+                        style: ButtonStyle::Success,
+                        custom_id: SUBMIT_ID.to_string(),
+                        label: Some(
+                            match player.submitted {
+                                true => "Submitted",
+                                false => "Submit",
+                            }
+                            .into(),
+                        ),
+                        disabled: !filled || player.submitted || extra > 0,
+                    }));
+
+                    if extra > 0 {
+                        let discarding = player.selected.iter().flatten().count();
+                        msg.append_component(ActionRowComponent::Button(Button::Action {
+                            style: ButtonStyle::Danger,
+                            custom_id: DISCARD_ID.to_string(),
+                            label: Some("Discard".into()),
+                            disabled: !(1..=extra).contains(&discarding),
+                        }));
+                        msg.fields.push(Field::new(
+                            "Discard",
+                            format!(
+                                "You hold `{}` more cards than this round allows, \
+                                select and discard them before submitting.",
+                                extra
+                            ),
+                        ));
+                    }
+
+                    let answer = self.prompt.fill(&self.packs, &mut player.selected());
                     msg.fields.push(Field::new(
                         "Answer",
-                        self.prompt.fill(&self.packs, &mut player.selected()),
+                        truncate_escaped(&answer, FIELD_VALUE_LIMIT),
                     ));
                 }
 
+                // the first czar is not dealt a hand until their next round
+                if player.hand.is_empty() {
+                    msg.fields.push(Field::new(
+                        "Hand",
+                        "You will be dealt cards after judging this round",
+                    ));
+                    return None;
+                }
+
+                let hand = player
+                    .hand
+                    .iter()
+                    .copied()
+                    .enumerate()
+                    .map(|(i, c)| {
+                        format!(
+                            "{}. ``{}``",
+                            i + 1,
+                            c.fill(&self.packs, &mut std::iter::empty())
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
                 msg.fields.push(Field::new(
                     "Hand",
-                    player
-                        .hand
-                        .iter()
-                        .copied()
-                        .enumerate()
-                        .map(|(i, c)| {
-                            format!(
-                                "{}. ``{}``",
-                                i + 1,
-                                c.fill(&self.packs, &mut std::iter::empty())
-                            )
-                        })
-                        .collect::<Vec<_>>()
-                        .join("\n"),
+                    truncate_escaped(&hand, FIELD_VALUE_LIMIT),
                 ));
 
                 if changed {
@@ -0,0 +1,59 @@
+use std::fmt::{self, Display};
+
+const SEPARATOR: char = ':';
+
+// framework components such as rules and join/leave
+pub const GAME: &str = "game";
+// actions defined by the game itself
+pub const ACTION: &str = "action";
+
+pub const SELECT: &str = "select";
+pub const TOGGLE: &str = "toggle";
+pub const NUMBER: &str = "number";
+pub const GRID: &str = "grid";
+pub const LABEL: &str = "label";
+pub const PAGE: &str = "page";
+
+// a component id of the form "namespace:name:arg", where the argument is optional
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CustomId<'a> {
+    pub namespace: &'a str,
+    pub name: &'a str,
+    pub arg: Option<&'a str>,
+}
+
+impl<'a> CustomId<'a> {
+    pub const fn new(namespace: &'a str, name: &'a str) -> Self {
+        Self {
+            namespace,
+            name,
+            arg: None,
+        }
+    }
+    pub const fn arg(mut self, arg: &'a str) -> Self {
+        self.arg = Some(arg);
+        self
+    }
+    pub fn parse(s: &'a str) -> Option<Self> {
+        // the argument is last, so it may contain the separator
+        let mut parts = s.splitn(3, SEPARATOR);
+        Some(Self {
+            namespace: parts.next()?,
+            name: parts.next()?,
+            arg: parts.next(),
+        })
+    }
+    pub fn is(&self, namespace: &str, name: &str) -> bool {
+        self.namespace == namespace && self.name == name
+    }
+}
+
+impl Display for CustomId<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}{}", self.namespace, SEPARATOR, self.name)?;
+        if let Some(arg) = self.arg {
+            write!(f, "{}{}", SEPARATOR, arg)?;
+        }
+        Ok(())
+    }
+}
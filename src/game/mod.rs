@@ -1,25 +1,33 @@
-use std::{collections::HashMap, str::FromStr, unreachable};
+use std::{
+    collections::{HashMap, VecDeque},
+    str::FromStr,
+};
 
 use async_trait::async_trait;
 use futures_util::future::join_all;
 
 use discord::{
     channel::{Channel, ChannelResource},
+    guild::Guild,
     interaction::{
         ApplicationCommand, CreateReply, CreateUpdate, InteractionResource,
         InteractionResponseIdentifier, InteractionToken, MessageComponent, MessageInteraction,
         MessageInteractionResource, ReplyFlag, Webhook,
     },
     message::{
-        ActionRow, Author, CreateMessage, Embed, Field, Message, MessageResource, PatchMessage,
+        ActionRow, ActionRowComponent, Author, Button, CreateMessage, Embed, Field, Message,
+        MessageIdentifier, MessageResource, PatchMessage,
     },
-    request::{Bot, Result},
+    request::{Bot, RequestError, Result},
     resource::Snowflake,
     user::User,
 };
 
-use self::widget::Event;
+use self::{custom_id::CustomId, widget::Event};
 
+pub mod custom_id;
+pub mod settings;
+pub mod users;
 pub mod widget;
 
 pub const B64_TABLE: [char; 64] = [
@@ -29,20 +37,42 @@ pub const B64_TABLE: [char; 64] = [
     '5', '6', '7', '8', '9', '+', '/',
 ];
 
+pub const RULES_ID: CustomId<'static> = CustomId::new(custom_id::GAME, "rules");
+pub const JOIN_ID: CustomId<'static> = CustomId::new(custom_id::GAME, "join");
+
+// how many interaction ids are remembered to detect duplicate deliveries
+const SEEN_CAPACITY: usize = 64;
+
 pub struct InteractionDispatcher {
     games: Vec<GameTask>,
+    seen: VecDeque<Snowflake<MessageInteraction<MessageComponent>>>,
 }
 
 pub struct GameTask {
+    owner: Snowflake<User>,
     ui: GameUI,
     game: Box<dyn Logic>,
 }
 
 impl InteractionDispatcher {
     pub fn new() -> Self {
-        InteractionDispatcher { games: Vec::new() }
+        InteractionDispatcher {
+            games: Vec::new(),
+            seen: VecDeque::with_capacity(SEEN_CAPACITY),
+        }
     }
     pub async fn dispatch(&mut self, i: MessageInteraction<MessageComponent>) {
+        // discord may deliver the same interaction twice, which must not be applied twice
+        let id = i.token.id();
+        if self.seen.contains(&id) {
+            return;
+        }
+        if self.seen.len() == SEEN_CAPACITY {
+            self.seen.pop_front();
+        }
+        self.seen.push_back(id);
+
+        users::remember(&i.user, i.member.as_ref());
         let msg = i.message.id.snowflake();
 
         let pos = match self
@@ -52,12 +82,16 @@ impl InteractionDispatcher {
         {
             Some(pos) => pos,
             _ => {
-                // give a "no response" error
-                i.forget();
+                // the game has ended or the bot has restarted since
+                let reply = CreateReply::default()
+                    .content("This game has ended.".into())
+                    .flags(ReplyFlag::Ephemeral.into());
+                acknowledge(i.reply(&Webhook, reply).await);
                 return;
             }
         };
 
+        // requests are only ever awaited here, so they do not outlive the game
         let task = &mut self.games[pos];
         let is_done = task.game.logic(&mut task.ui, i).await;
 
@@ -65,6 +99,26 @@ impl InteractionDispatcher {
             self.games.swap_remove(pos);
         }
     }
+    pub async fn stop(&mut self, owner: Snowflake<User>) -> bool {
+        let Some(pos) = self.games.iter().position(|s| s.owner == owner) else {
+            return false;
+        };
+
+        let mut task = self.games.swap_remove(pos);
+        task.game.exit(&mut task.ui).await;
+        true
+    }
+    pub async fn tick(&mut self) {
+        let mut i = 0;
+        while i < self.games.len() {
+            let task = &mut self.games[i];
+            if task.game.tick(&mut task.ui).await {
+                self.games.swap_remove(i);
+            } else {
+                i += 1;
+            }
+        }
+    }
     pub fn register(&mut self, task: GameTask) {
         self.games.push(task);
     }
@@ -77,6 +131,11 @@ pub struct GameUI {
     msg_id: Snowflake<Message>,
     msg: Option<InteractionResponseIdentifier>,
     panel: &'static str,
+
+    // the base message if it was sent by the bot instead of an interaction
+    base: Option<MessageIdentifier>,
+    channel: Snowflake<Channel>,
+    client: Bot,
     user: Snowflake<User>,
 
     replies: HashMap<Snowflake<Message>, (&'static str, InteractionResponseIdentifier)>,
@@ -84,7 +143,7 @@ pub struct GameUI {
     thread: Option<Snowflake<Channel>>,
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct GameMessage {
     pub fields: Vec<Field>,
     pub components: Vec<ActionRow>,
@@ -97,6 +156,46 @@ impl GameMessage {
     pub fn is_empty(&self) -> bool {
         self.fields.is_empty() && self.components.is_empty()
     }
+    pub fn disable_all_components(&mut self) {
+        for component in self
+            .components
+            .iter_mut()
+            .flat_map(|row| row.components.iter_mut())
+        {
+            match component {
+                ActionRowComponent::Button(Button::Action { disabled, .. }) => *disabled = true,
+                ActionRowComponent::TextSelectMenu(menu) => menu.disabled = true,
+                _ => (),
+            }
+        }
+    }
+    // the main panel of a game is signed with its name and color
+    fn signed_embed(fields: Vec<Field>, name: &str, color: u32) -> Embed {
+        Embed::default()
+            .author(Author::new(name))
+            .color(color)
+            .fields(fields)
+    }
+    pub fn into_create_message(self, name: &str, color: u32) -> CreateMessage {
+        CreateMessage::default()
+            .embeds(vec![Self::signed_embed(self.fields, name, color)])
+            .components(self.components)
+    }
+    pub fn into_patch_message(self, name: &str, color: u32) -> PatchMessage {
+        PatchMessage::default()
+            .embeds(vec![Self::signed_embed(self.fields, name, color)])
+            .components(self.components)
+    }
+    pub fn into_create_update(self, name: &str, color: u32) -> CreateUpdate {
+        CreateUpdate::default()
+            .embeds(vec![Self::signed_embed(self.fields, name, color)])
+            .components(self.components)
+    }
+    pub fn into_create_reply(self, name: &str, color: u32) -> CreateReply {
+        CreateReply::default()
+            .embeds(vec![Self::signed_embed(self.fields, name, color)])
+            .components(self.components)
+    }
 }
 
 impl From<Message> for GameMessage {
@@ -108,38 +207,82 @@ impl From<Message> for GameMessage {
     }
 }
 
+// another click may have already responded to this interaction
+fn acknowledge<T>(result: Result<T>) -> Option<T> {
+    match result {
+        Err(err) if err.is_already_acknowledged() => None,
+        result => Some(result.unwrap()),
+    }
+}
+
 impl GameUI {
     pub fn is_in_thread(&self) -> bool {
         self.thread.is_some()
     }
-    pub async fn edit(&self, id: Snowflake<Message>, msg: GameMessage) {
-        if id == self.msg_id {
-            // sign if we are updating the base message
-            self.msg
-                .as_ref()
-                .unwrap()
+    pub async fn edit(&mut self, id: Snowflake<Message>, msg: GameMessage) {
+        if id != self.msg_id {
+            let result = self.replies[&id]
+                .1
                 .patch(
                     &Webhook,
                     PatchMessage::default()
-                        .embeds(vec![Embed::default()
-                            .author(Author::new(self.name))
-                            .color(self.color)
-                            .fields(msg.fields)])
+                        .embeds(vec![Embed::default().fields(msg.fields)])
                         .components(msg.components),
                 )
-                .await
-                .unwrap();
-        } else {
-            self.replies[&id]
-                .1
+                .await;
+
+            // replies are only temporary, so they are left as they are
+            if let Err(err) = result {
+                println!("could not edit reply: {:?}", err);
+            }
+            return;
+        }
+
+        // sign if we are updating the base message
+        // prefer the last interaction, as long as its token has not expired
+        if let Some(interaction) = self.msg.as_ref().filter(|i| !i.is_expired()) {
+            let result = interaction
                 .patch(
                     &Webhook,
-                    PatchMessage::default()
-                        .embeds(vec![Embed::default().fields(msg.fields)])
-                        .components(msg.components),
+                    msg.clone().into_patch_message(self.name, self.color),
                 )
+                .await;
+            match result {
+                // discord may consider the token expired before we do
+                Err(RequestError::Expired) => self.msg = None,
+                result => {
+                    result.unwrap();
+                    return;
+                }
+            }
+        }
+
+        // messages the bot sent itself can be edited at any time
+        if let Some(base) = self.base {
+            base.patch(&self.client, msg.into_patch_message(self.name, self.color))
                 .await
                 .unwrap();
+            return;
+        }
+
+        // the interaction has expired, so continue in a new message
+        let channel = self.thread.unwrap_or(self.channel);
+        let message = channel
+            .send_message(&self.client, msg.into_create_message(self.name, self.color))
+            .await
+            .unwrap();
+        self.msg_id = message.id.snowflake();
+        self.base = Some(message.id);
+        self.msg = None;
+    }
+    fn create_reply(msg: GameMessage, ephemeral: bool) -> CreateReply {
+        // we do not sign replies
+        let reply = CreateReply::default()
+            .embeds(vec![Embed::default().fields(msg.fields)])
+            .components(msg.components);
+        match ephemeral {
+            true => reply.flags(ReplyFlag::Ephemeral.into()),
+            false => reply,
         }
     }
     pub async fn reply_panel<P: Into<&'static str>>(
@@ -147,60 +290,45 @@ impl GameUI {
         i: MessageInteraction<MessageComponent>,
         msg: GameMessage,
         panel: P,
+        ephemeral: bool,
     ) {
-        // we do not sign replies
-
-        let response = i
-            .reply(
-                &Webhook,
-                CreateReply::default()
-                    .embeds(vec![Embed::default().fields(msg.fields)])
-                    .components(msg.components)
-                    .flags(ReplyFlag::Ephemeral.into()),
-            )
-            .await
-            .unwrap();
+        let Some(response) =
+            acknowledge(i.reply(&Webhook, Self::create_reply(msg, ephemeral)).await)
+        else {
+            return;
+        };
 
         let id = response.get(&Webhook).await.unwrap().id.snowflake();
         self.replies.insert(id, (panel.into(), response));
     }
-    pub async fn reply(&mut self, i: MessageInteraction<MessageComponent>, msg: GameMessage) {
-        // we do not sign replies
-        i.reply(
-            &Webhook,
-            CreateReply::default()
-                .embeds(vec![Embed::default().fields(msg.fields)])
-                .components(msg.components)
-                .flags(ReplyFlag::Ephemeral.into()),
-        )
-        .await
-        .unwrap();
+    pub async fn reply(
+        &mut self,
+        i: MessageInteraction<MessageComponent>,
+        msg: GameMessage,
+        ephemeral: bool,
+    ) {
+        acknowledge(i.reply(&Webhook, Self::create_reply(msg, ephemeral)).await);
     }
     pub async fn update(&mut self, i: MessageInteraction<MessageComponent>, msg: GameMessage) {
         if i.message.id.snowflake() == self.msg_id {
             // sign if we are updating the base message
-            self.msg = Some(
+            let response = acknowledge(
+                i.update(&Webhook, msg.into_create_update(self.name, self.color))
+                    .await,
+            );
+            if let Some(response) = response {
+                self.msg = Some(response);
+            }
+        } else {
+            acknowledge(
                 i.update(
                     &Webhook,
                     CreateUpdate::default()
-                        .embeds(vec![Embed::default()
-                            .author(Author::new(self.name))
-                            .color(self.color)
-                            .fields(msg.fields)])
+                        .embeds(vec![Embed::default().fields(msg.fields)])
                         .components(msg.components),
                 )
-                .await
-                .unwrap(),
+                .await,
             );
-        } else {
-            i.update(
-                &Webhook,
-                CreateUpdate::default()
-                    .embeds(vec![Embed::default().fields(msg.fields)])
-                    .components(msg.components),
-            )
-            .await
-            .unwrap();
         }
     }
     pub async fn update_reply(
@@ -210,30 +338,55 @@ impl GameUI {
     ) {
         if i.message.id.snowflake() == self.msg_id {
             // sign if we are updating the base message
-            let interaction = i
-                .reply(
+            let Some(interaction) = acknowledge(
+                i.reply(&Webhook, msg.into_create_reply(self.name, self.color))
+                    .await,
+            ) else {
+                return;
+            };
+            self.msg_id = interaction.get(&Webhook).await.unwrap().id.snowflake();
+            self.msg = Some(interaction);
+            self.base = None;
+        } else {
+            acknowledge(
+                i.reply(
                     &Webhook,
                     CreateReply::default()
-                        .embeds(vec![Embed::default()
-                            .author(Author::new(self.name))
-                            .color(self.color)
-                            .fields(msg.fields)])
+                        .embeds(vec![Embed::default().fields(msg.fields)])
                         .components(msg.components),
                 )
-                .await
-                .unwrap();
-            self.msg_id = interaction.get(&Webhook).await.unwrap().id.snowflake();
-            self.msg = Some(interaction);
-        } else {
-            i.reply(
-                &Webhook,
-                CreateReply::default()
-                    .embeds(vec![Embed::default().fields(msg.fields)])
+                .await,
+            );
+        }
+    }
+    // sends a new public message without needing an interaction to reply to
+    pub async fn push(&self, msg: GameMessage) {
+        // prefer the last interaction, as long as its token has not expired
+        if let Some(interaction) = self.msg.as_ref().filter(|i| !i.is_expired()) {
+            let reply = CreateReply::default()
+                .embeds(vec![Embed::default().fields(msg.fields.clone())])
+                .components(msg.components.clone());
+            match interaction.followup(&Webhook, reply).await {
+                // discord may consider the token expired before we do
+                Err(RequestError::Expired) => (),
+                result => {
+                    result.unwrap();
+                    return;
+                }
+            }
+        }
+
+        let embeds = vec![Embed::default().fields(msg.fields)];
+        let channel = self.thread.unwrap_or(self.channel);
+        channel
+            .send_message(
+                &self.client,
+                CreateMessage::default()
+                    .embeds(embeds)
                     .components(msg.components),
             )
             .await
             .unwrap();
-        }
     }
     pub async fn delete_replies(&mut self) {
         let _ = join_all(self.replies.drain().map(|(_, (_, id))| id.delete(&Webhook))).await;
@@ -243,6 +396,8 @@ impl GameUI {
 #[async_trait]
 trait Logic {
     async fn logic(&mut self, ui: &mut GameUI, i: MessageInteraction<MessageComponent>) -> bool;
+    async fn exit(&mut self, ui: &mut GameUI);
+    async fn tick(&mut self, ui: &mut GameUI) -> bool;
 }
 
 #[async_trait]
@@ -250,6 +405,44 @@ impl<T> Logic for T
 where
     T: Game + Send,
 {
+    async fn exit(&mut self, ui: &mut GameUI) {
+        ui.delete_replies().await;
+        ui.color = self.color();
+
+        let panel = T::Panel::from_str(ui.panel).unwrap_or_default();
+        let mut msg = GameMessage::default();
+        self.create_panel(&mut msg, &Event::none(), panel, ui.user);
+        msg.disable_all_components();
+        ui.edit(ui.msg_id, msg).await;
+    }
+    async fn tick(&mut self, ui: &mut GameUI) -> bool {
+        let response = self.on_tick();
+        ui.color = self.color();
+
+        match response {
+            ActionResponse::EditMain | ActionResponse::NextMain(_) => {
+                if let ActionResponse::NextMain(_) = response {
+                    ui.delete_replies().await;
+                }
+
+                let panel = T::Panel::from_str(ui.panel).unwrap_or_default();
+                let mut msg = GameMessage::default();
+                self.create_panel(&mut msg, &Event::none(), panel, ui.user);
+                ui.edit(ui.msg_id, msg).await;
+                false
+            }
+            ActionResponse::Exit => {
+                self.exit(ui).await;
+                true
+            }
+            ActionResponse::Announce(msg) => {
+                ui.push(msg).await;
+                false
+            }
+            // there is no interaction to reply to
+            _ => false,
+        }
+    }
     async fn logic(
         &mut self,
         ui: &mut GameUI,
@@ -265,11 +458,32 @@ where
                 )
             }
         };
-        let panel = match T::Panel::from_str(panel) {
-            Ok(panel) => panel,
-            Err(_) => unreachable!(),
+        // rules can be shown from any panel
+        let component_id = CustomId::parse(&interaction.data.custom_id);
+        if component_id == Some(RULES_ID) {
+            ui.reply(interaction, T::rules(), true).await;
+            return false;
+        }
+
+        // players that join the lobby are added to the thread
+        if let Some(thread) = ui.thread.filter(|_| component_id == Some(JOIN_ID)) {
+            if let Err(err) = thread
+                .add_thread_member(&ui.client, interaction.user.id)
+                .await
+            {
+                println!("could not add thread member: {:?}", err);
+            }
+        }
+
+        // components from an older version may name panels that no longer exist
+        let (Ok(panel), Ok(main_panel)) = (T::Panel::from_str(panel), T::Panel::from_str(ui.panel))
+        else {
+            interaction.forget();
+            return false;
         };
-        let mut action = T::Action::from_str(&interaction.data.custom_id).ok();
+        let mut action = component_id
+            .filter(|id| id.namespace == custom_id::ACTION)
+            .and_then(|id| T::Action::from_str(id.name).ok());
 
         let mut panel_msg = GameMessage::default();
         if action.is_none() {
@@ -284,6 +498,18 @@ where
         match action {
             Some(action) => {
                 let response = self.on_action(action, panel, &interaction.user);
+                ui.color = self.color();
+
+                let response = match response {
+                    Ok(response) => response,
+                    Err(err) => {
+                        // recoverable, so only the user that caused it is told
+                        println!("error in {}: {}", ui.name, err.0);
+                        let msg = GameMessage::new(vec![Field::new("Error", err.0)], vec![]);
+                        ui.reply(interaction, msg, true).await;
+                        return false;
+                    }
+                };
                 match response {
                     ActionResponse::EditMain => {
                         // update panel if it should be updated
@@ -293,15 +519,7 @@ where
 
                         // edit main panel
                         let mut msg = GameMessage::default();
-                        self.create_panel(
-                            &mut msg,
-                            &Event::none(),
-                            match T::Panel::from_str(ui.panel) {
-                                Ok(panel) => panel,
-                                Err(_) => unreachable!(),
-                            },
-                            ui.user,
-                        );
+                        self.create_panel(&mut msg, &Event::none(), main_panel, ui.user);
                         ui.edit(ui.msg_id, msg).await;
                         false
                     }
@@ -327,18 +545,29 @@ where
                         // create new panel
                         let mut msg = GameMessage::default();
                         self.create_panel(&mut msg, &Event::none(), panel, interaction.user.id);
-                        ui.reply_panel(interaction, msg, panel).await;
+                        ui.reply_panel(interaction, msg, panel, true).await;
                         false
                     }
                     ActionResponse::Error(msg) => {
                         // send error message
-                        ui.reply(interaction, msg).await;
+                        ui.reply(interaction, msg, true).await;
+                        false
+                    }
+                    ActionResponse::Announce(msg) => {
+                        // send public message, after updating the panel if it should be updated
+                        if panel_msg.is_empty() {
+                            ui.reply(interaction, msg, false).await;
+                        } else {
+                            ui.update(interaction, panel_msg).await;
+                            ui.push(msg).await;
+                        }
                         false
                     }
                     ActionResponse::Exit => {
                         // exit
                         ui.delete_replies().await;
                         if !panel_msg.is_empty() {
+                            panel_msg.disable_all_components();
                             ui.update(interaction, panel_msg).await;
                         }
                         true
@@ -398,6 +627,7 @@ pub enum ActionResponse<Panel> {
     NextMain(bool),
 
     Reply(Panel),
+    Announce(GameMessage),
 
     Error(GameMessage),
     Exit,
@@ -405,6 +635,17 @@ pub enum ActionResponse<Panel> {
     None,
 }
 
+#[derive(Debug)]
+pub struct GameError(pub String);
+
+impl GameError {
+    pub fn new(msg: impl Into<String>) -> Self {
+        Self(msg.into())
+    }
+}
+
+pub type ActionResult<Panel> = std::result::Result<ActionResponse<Panel>, GameError>;
+
 #[async_trait]
 pub trait Game: Sized + 'static {
     type Action: FromStr + Into<&'static str> + Send;
@@ -413,7 +654,19 @@ pub trait Game: Sized + 'static {
     const NAME: &'static str;
     const COLOR: u32;
 
-    fn new(user: User) -> Self;
+    // the embed color of the main panel, which may depend on the game state
+    fn color(&self) -> u32 {
+        Self::COLOR
+    }
+
+    // scoreboard indicators
+    const CZAR_EMOJI: &'static str = "👑";
+    const DONE_EMOJI: &'static str = "✅";
+    const WAITING_EMOJI: &'static str = "💭";
+
+    fn new(user: User, guild: Option<Snowflake<Guild>>) -> Self;
+
+    fn rules() -> GameMessage;
 
     fn create_panel(
         &mut self,
@@ -428,75 +681,76 @@ pub trait Game: Sized + 'static {
         action: Self::Action,
         panel: Self::Panel,
         user: &User,
-    ) -> ActionResponse<Self::Panel>;
+    ) -> ActionResult<Self::Panel>;
+
+    // called periodically by the dispatcher for time based game logic
+    fn on_tick(&mut self) -> ActionResponse<Self::Panel> {
+        ActionResponse::None
+    }
 
     async fn start(
         token: InteractionToken<ApplicationCommand>,
         user: User,
-        thread: Option<&Bot>,
+        guild: Option<Snowflake<Guild>>,
+        channel: Snowflake<Channel>,
+        client: &Bot,
+        thread: bool,
     ) -> Result<GameTask> {
         let user_id = user.id;
-        let mut me = Self::new(user);
+        let mut me = Self::new(user, guild);
 
         // send lobby message
         let mut msg = GameMessage::default();
         me.create_panel(&mut msg, &Event::none(), Self::Panel::default(), user_id);
 
+        // acknowledge first, as creating the game may take a while
+        let id = token.defer(&Webhook).await?;
+
         let (id, msg, thread) = match thread {
-            Some(discord) => {
+            true => {
                 // TODO: close thread on end
                 // TODO: give thread better name
-                let id = token
-                    .reply(
+                let thread = id
+                    .patch(
                         &Webhook,
-                        CreateReply::default()
+                        PatchMessage::default()
                             .content(format!("A new game of ``{}`` is starting!", Self::NAME)),
                     )
-                    .await?;
-                let channel = id
-                    .get(&Webhook)
                     .await?
-                    .start_thread(discord, Self::NAME.into())
+                    .start_thread(client, Self::NAME.into())
                     .await?;
-                let msg = channel
-                    .send_message(
-                        discord,
-                        CreateMessage::default()
-                            .embeds(vec![Embed::default()
-                                .author(Author::new(Self::NAME))
-                                .color(Self::COLOR)
-                                .fields(msg.fields)])
-                            .components(msg.components),
-                    )
+
+                // make sure the host can post in the thread, like players that join
+                if let Err(err) = thread.add_thread_member(client, user_id).await {
+                    println!("could not add thread member: {:?}", err);
+                }
+
+                let msg = thread
+                    .send_message(client, msg.into_create_message(Self::NAME, me.color()))
                     .await?;
-                (None, msg, Some(channel.id))
+                (None, msg, Some(thread.id))
             }
-            None => {
-                let id = token
-                    .reply(
-                        &Webhook,
-                        CreateReply::default()
-                            .embeds(vec![Embed::default()
-                                .author(Author::new(Self::NAME))
-                                .color(Self::COLOR)
-                                .fields(msg.fields)])
-                            .components(msg.components),
-                    )
+            false => {
+                let msg = id
+                    .patch(&Webhook, msg.into_patch_message(Self::NAME, me.color()))
                     .await?;
-                let msg = id.get(&Webhook).await?;
                 (Some(id), msg, None)
             }
         };
 
         // create task
         Ok(GameTask {
+            owner: user_id,
             ui: GameUI {
                 user: user_id,
                 name: Self::NAME,
-                color: Self::COLOR,
+                color: me.color(),
                 msg: id,
                 msg_id: msg.id.snowflake(),
                 panel: Self::Panel::default().into(),
+                base: thread.map(|_| msg.id),
+                channel,
+                client: client.clone(),
                 replies: HashMap::new(),
                 thread,
             },
@@ -0,0 +1,248 @@
+use discord::{
+    message::{ActionRow, ActionRowComponent, Button, ButtonStyle},
+    resource::Snowflake,
+    user::User,
+};
+
+use super::{
+    custom_id::{CustomId, PAGE},
+    widget::Event,
+    GameMessage,
+};
+
+// discord allows 5 rows of components
+const MAX_ROWS: usize = 5;
+
+pub enum SettingsOption {
+    MultiSelect {
+        items: Vec<String>,
+        selected: Vec<usize>,
+    },
+    Number {
+        value: i32,
+        min: i32,
+        max: i32,
+        step: i32,
+    },
+    Flag(bool),
+    Choice {
+        items: Vec<String>,
+        value: usize,
+    },
+    Players(Vec<Snowflake<User>>),
+}
+
+impl SettingsOption {
+    // flags and choices are single buttons, so consecutive ones share a row
+    fn is_button(&self) -> bool {
+        matches!(
+            self,
+            SettingsOption::Flag(_) | SettingsOption::Choice { .. }
+        )
+    }
+    // players are shown on every page, so anyone can always join
+    fn is_pinned(&self) -> bool {
+        matches!(self, SettingsOption::Players(_))
+    }
+}
+
+// the amount of rows the options take up when rendered in order
+fn rows<'a>(options: impl IntoIterator<Item = &'a SettingsOption>) -> usize {
+    let mut rows = 0;
+    let mut buttons = 0;
+    for option in options {
+        if option.is_button() {
+            if buttons % 5 == 0 {
+                rows += 1;
+            }
+            buttons += 1;
+        } else {
+            rows += 1;
+            buttons = 0;
+        }
+    }
+    rows
+}
+
+// typed handles to the options of a form, returned when adding them
+#[derive(Clone, Copy)]
+pub struct SelectKey(usize);
+#[derive(Clone, Copy)]
+pub struct NumberKey(usize);
+#[derive(Clone, Copy)]
+pub struct FlagKey(usize);
+#[derive(Clone, Copy)]
+pub struct ChoiceKey(usize);
+#[derive(Clone, Copy)]
+pub struct PlayersKey(usize);
+
+#[derive(Default)]
+pub struct SettingsForm {
+    options: Vec<(String, SettingsOption)>,
+    page: usize,
+}
+
+impl SettingsForm {
+    fn push(&mut self, name: String, option: SettingsOption) -> usize {
+        self.options.push((name, option));
+        self.options.len() - 1
+    }
+    pub fn multi_select<S: Into<String>>(
+        &mut self,
+        name: S,
+        items: Vec<String>,
+        selected: Vec<usize>,
+    ) -> SelectKey {
+        SelectKey(self.push(name.into(), SettingsOption::MultiSelect { items, selected }))
+    }
+    pub fn number<S: Into<String>>(
+        &mut self,
+        name: S,
+        value: i32,
+        min: i32,
+        max: i32,
+        step: i32,
+    ) -> NumberKey {
+        let value = value.clamp(min, max);
+        NumberKey(self.push(
+            name.into(),
+            SettingsOption::Number {
+                value,
+                min,
+                max,
+                step,
+            },
+        ))
+    }
+    pub fn flag<S: Into<String>>(&mut self, name: S, value: bool) -> FlagKey {
+        FlagKey(self.push(name.into(), SettingsOption::Flag(value)))
+    }
+    pub fn choice<S: Into<String>>(
+        &mut self,
+        name: S,
+        items: Vec<String>,
+        value: usize,
+    ) -> ChoiceKey {
+        let name = name.into();
+        assert!(!items.is_empty(), "choice {} has no items", name);
+        let value = value.min(items.len() - 1);
+        ChoiceKey(self.push(name, SettingsOption::Choice { items, value }))
+    }
+    pub fn players<S: Into<String>>(&mut self, name: S, users: Vec<Snowflake<User>>) -> PlayersKey {
+        PlayersKey(self.push(name.into(), SettingsOption::Players(users)))
+    }
+
+    // splits the unpinned options into pages, but only if they do not all fit on one
+    fn pages(&self) -> Vec<Vec<usize>> {
+        let (pinned, options): (Vec<_>, Vec<_>) =
+            (0..self.options.len()).partition(|&i| self.options[i].1.is_pinned());
+
+        // one row is kept free for the page and game buttons
+        let available = MAX_ROWS - 1;
+        if rows(self.options.iter().map(|(_, o)| o)) <= available {
+            return vec![options];
+        }
+
+        let per_page = available.saturating_sub(pinned.len()).max(1);
+        let mut pages = vec![Vec::new()];
+        for i in options {
+            let page = pages.last_mut().unwrap();
+            page.push(i);
+            if page.len() > 1 && rows(page.iter().map(|&i| &self.options[i].1)) > per_page {
+                page.pop();
+                pages.push(vec![i]);
+            }
+        }
+        pages
+    }
+    pub fn render(&mut self, msg: &mut GameMessage, event: &Event) {
+        let pages = self.pages();
+        match event.custom_id() {
+            Some(id) if id.is(PAGE, "prev") => self.page = self.page.saturating_sub(1),
+            Some(id) if id.is(PAGE, "next") => self.page += 1,
+            _ => (),
+        }
+        self.page = self.page.min(pages.len() - 1);
+
+        let pinned = (0..self.options.len()).filter(|&i| self.options[i].1.is_pinned());
+        let indices: Vec<_> = pages[self.page].iter().copied().chain(pinned).collect();
+
+        let mut after_button = false;
+        for i in indices {
+            let (name, option) = &mut self.options[i];
+
+            // a run of buttons starts on a row of its own, like rows() expects
+            let is_button = option.is_button();
+            if is_button && !after_button {
+                msg.components.push(ActionRow::new(Vec::new()));
+            }
+            after_button = is_button;
+
+            match option {
+                SettingsOption::MultiSelect { items, selected } => {
+                    msg.create_select(event, name.clone(), items.iter().cloned(), selected)
+                }
+                SettingsOption::Number {
+                    value,
+                    min,
+                    max,
+                    step,
+                } => msg.create_number(event, name.clone(), value, *min, *max, *step),
+                SettingsOption::Flag(value) => msg.append_toggle(event, name.clone(), value),
+                SettingsOption::Choice { items, value } => {
+                    msg.append_choice(event, name.clone(), items, value)
+                }
+                SettingsOption::Players(users) => msg.create_join(event, users),
+            }
+        }
+
+        if pages.len() > 1 {
+            // the page buttons do not share a row with the options above
+            if after_button {
+                msg.components.push(ActionRow::new(Vec::new()));
+            }
+            let button = |name: &str, label: &str, disabled: bool| {
+                ActionRowComponent::Button(Button::Action {
+                    style: ButtonStyle::Secondary,
+                    custom_id: CustomId::new(PAGE, name).to_string(),
+                    label: Some(label.into()),
+                    disabled,
+                })
+            };
+            msg.append_component(button("prev", "Previous Page", self.page == 0));
+            msg.append_component(button("next", "Next Page", self.page == pages.len() - 1));
+        }
+    }
+
+    // keys are only handed out for the option they were created with
+    pub fn get_selected(&self, key: SelectKey) -> &[usize] {
+        match &self.options[key.0].1 {
+            SettingsOption::MultiSelect { selected, .. } => selected,
+            _ => unreachable!(),
+        }
+    }
+    pub fn get_number(&self, key: NumberKey) -> i32 {
+        match &self.options[key.0].1 {
+            SettingsOption::Number { value, .. } => *value,
+            _ => unreachable!(),
+        }
+    }
+    pub fn get_flag(&self, key: FlagKey) -> bool {
+        match &self.options[key.0].1 {
+            SettingsOption::Flag(value) => *value,
+            _ => unreachable!(),
+        }
+    }
+    pub fn get_choice(&self, key: ChoiceKey) -> usize {
+        match &self.options[key.0].1 {
+            SettingsOption::Choice { value, .. } => *value,
+            _ => unreachable!(),
+        }
+    }
+    pub fn get_players(&self, key: PlayersKey) -> &[Snowflake<User>] {
+        match &self.options[key.0].1 {
+            SettingsOption::Players(users) => users,
+            _ => unreachable!(),
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+};
+
+use discord::{guild::Member, resource::Snowflake, user::User};
+
+// names of users seen in interactions, so they can be shown without fetching or pinging
+static NAMES: OnceLock<Mutex<HashMap<Snowflake<User>, String>>> = OnceLock::new();
+
+fn names() -> &'static Mutex<HashMap<Snowflake<User>, String>> {
+    NAMES.get_or_init(Default::default)
+}
+
+pub fn remember(user: &User, member: Option<&Member>) {
+    let mut names = names().lock().unwrap();
+    match member.and_then(Member::display_name) {
+        // nicknames are only known within guilds, so they take precedence
+        Some(name) => {
+            names.insert(user.id, name.into());
+        }
+        None => {
+            names
+                .entry(user.id)
+                .or_insert_with(|| user.username.clone());
+        }
+    }
+}
+
+pub fn display_name(user: Snowflake<User>) -> Option<String> {
+    names().lock().unwrap().get(&user).cloned()
+}
@@ -1,11 +1,41 @@
 use discord::{
     interaction::{MessageComponent, MessageInteraction},
-    message::{ActionRow, ActionRowComponent, Button, ButtonStyle, SelectOption, TextSelectMenu},
+    message::{
+        ActionRow, ActionRowComponent, Button, ButtonStyle, ComponentType, SelectOption,
+        TextSelectMenu,
+    },
     resource::Snowflake,
     user::User,
 };
 
-use super::{GameMessage, B64_TABLE};
+use super::{
+    custom_id::{CustomId, ACTION, GAME, GRID, LABEL, NUMBER, SELECT, TOGGLE},
+    GameMessage, B64_TABLE, JOIN_ID, RULES_ID,
+};
+
+// encodes an option index as a single character for use in values and custom ids
+pub fn encode_index(i: usize) -> char {
+    B64_TABLE[i]
+}
+
+// decodes the leading character of a value or custom id back into an index
+pub fn decode_index(s: &str) -> Option<usize> {
+    let first = s.chars().next()?;
+    B64_TABLE.iter().position(|&c| c == first)
+}
+
+// a disabled button that only serves as a label for its row
+// the row index keeps ids unique when two labels share their text
+pub fn label_button(text: String, row: usize) -> ActionRowComponent {
+    ActionRowComponent::Button(Button::Action {
+        style: ButtonStyle::Primary,
+        custom_id: CustomId::new(LABEL, &text)
+            .arg(&row.to_string())
+            .to_string(),
+        label: Some(text),
+        disabled: true,
+    })
+}
 
 pub struct Event<'a> {
     interaction: Option<&'a MessageInteraction<MessageComponent>>,
@@ -21,6 +51,10 @@ impl<'a> Event<'a> {
         }
     }
 
+    pub fn custom_id(&self) -> Option<CustomId<'a>> {
+        CustomId::parse(&self.interaction?.data.custom_id)
+    }
+
     pub fn matches<T>(
         &self,
         f: impl FnOnce(&'a MessageInteraction<MessageComponent>) -> Option<T>,
@@ -45,6 +79,12 @@ impl<'a> Event<'a> {
 }
 
 impl GameMessage {
+    pub fn append_component(&mut self, component: ActionRowComponent) {
+        match self.components.last_mut() {
+            Some(row) if !row.is_full() => row.components.push(component),
+            _ => self.components.push(ActionRow::new(vec![component])),
+        }
+    }
     pub fn append_action(
         &mut self,
         action: impl Into<&'static str>,
@@ -53,14 +93,60 @@ impl GameMessage {
     ) {
         let button = ActionRowComponent::Button(Button::Action {
             style,
-            custom_id: Into::<&'static str>::into(action).into(),
+            custom_id: CustomId::new(ACTION, action.into()).to_string(),
             label: Some(name),
             disabled: false,
         });
-        match self.components.last_mut() {
-            Some(row) if !row.is_full() => row.components.push(button),
-            _ => self.components.push(ActionRow::new(vec![button])),
+        self.append_component(button);
+    }
+    pub fn append_rules(&mut self) {
+        self.append_component(ActionRowComponent::Button(Button::Action {
+            style: ButtonStyle::Secondary,
+            custom_id: RULES_ID.to_string(),
+            label: Some("Rules".into()),
+            disabled: false,
+        }));
+    }
+    pub fn append_toggle(&mut self, event: &Event, name: String, val: &mut bool) {
+        if event.custom_id().is_some_and(|id| id.is(TOGGLE, &name)) {
+            *val = !*val;
+        }
+
+        let button = ActionRowComponent::Button(Button::Action {
+            style: match *val {
+                true => ButtonStyle::Success,
+                false => ButtonStyle::Secondary,
+            },
+            custom_id: CustomId::new(TOGGLE, &name).to_string(),
+            label: Some(name),
+            disabled: false,
+        });
+        self.append_component(button);
+    }
+    pub fn append_choice(
+        &mut self,
+        event: &Event,
+        name: String,
+        items: &[String],
+        val: &mut usize,
+    ) {
+        // there is nothing to choose from
+        if items.is_empty() {
+            return;
         }
+
+        // every click moves on to the next item
+        if event.custom_id().is_some_and(|id| id.is(TOGGLE, &name)) {
+            *val = (*val + 1) % items.len();
+        }
+
+        let button = ActionRowComponent::Button(Button::Action {
+            style: ButtonStyle::Secondary,
+            custom_id: CustomId::new(TOGGLE, &name).to_string(),
+            label: Some(format!("{}: {}", name, items[*val])),
+            disabled: false,
+        });
+        self.append_component(button);
     }
     pub fn create_select(
         &mut self,
@@ -71,20 +157,12 @@ impl GameMessage {
     ) {
         // get selected values
         let changed = match event.matches(|i| {
-            if i.data.custom_id == name {
-                Some(&i.data.values)
-            } else {
-                None
-            }
+            let id = CustomId::parse(&i.data.custom_id)?;
+            let is_select = i.data.component_type == ComponentType::TextSelectMenu;
+            (is_select && id.is(SELECT, &name)).then_some(&i.data.values)
         }) {
             Some(v) => {
-                *selected = v
-                    .iter()
-                    .filter_map(|s| {
-                        let first = s.chars().next()?;
-                        B64_TABLE.iter().position(|&c| c == first)
-                    })
-                    .collect();
+                *selected = v.iter().filter_map(|s| decode_index(s)).collect();
                 true
             }
             _ => false,
@@ -97,7 +175,7 @@ impl GameMessage {
                 default: selected.contains(&i),
                 label: s,
                 description: None,
-                value: B64_TABLE[i].to_string(),
+                value: encode_index(i).to_string(),
             })
             .collect();
 
@@ -105,17 +183,35 @@ impl GameMessage {
             selected.retain(|&i| i < options.len());
         }
 
+        let menu = TextSelectMenu {
+            custom_id: CustomId::new(SELECT, &name).to_string(),
+            placeholder: Some(name),
+            min_values: 0,
+            max_values: options.len(),
+            options,
+            disabled: false,
+        };
+
+        // discord rejects the whole message if a menu is invalid, so show why it is missing
+        if let Err(err) = menu.validate() {
+            eprintln!("could not create select menu: {:?}", err);
+            let name = menu.placeholder.unwrap_or_default();
+            self.components
+                .push(ActionRow::new(vec![ActionRowComponent::Button(
+                    Button::Action {
+                        style: ButtonStyle::Secondary,
+                        custom_id: menu.custom_id,
+                        label: Some(format!("{}: unavailable", name)),
+                        disabled: true,
+                    },
+                )]));
+            return;
+        }
+
         // add component
         self.components
             .push(ActionRow::new(vec![ActionRowComponent::TextSelectMenu(
-                TextSelectMenu {
-                    custom_id: name.clone(),
-                    placeholder: Some(name),
-                    min_values: 0,
-                    max_values: options.len(),
-                    options,
-                    disabled: false,
-                },
+                menu,
             )]));
     }
     pub fn create_number(
@@ -125,48 +221,62 @@ impl GameMessage {
         val: &mut i32,
         min: i32,
         max: i32,
+        step: i32,
     ) {
         // get value
-        match event.matches(|i| i.data.custom_id.strip_prefix(&name)) {
-            Some("__min") => *val = val.saturating_sub(1).max(min),
-            Some("__max") => *val = val.saturating_add(1).min(max),
+        match event.custom_id() {
+            Some(id) if id.is(NUMBER, &name) => {
+                if let Some(delta) = id.arg.and_then(|arg| arg.parse::<i32>().ok()) {
+                    *val = val.saturating_add(delta).clamp(min, max);
+                }
+            }
             _ => (),
         };
 
-        // add components
-        self.components.push(ActionRow::new(vec![
-            ActionRowComponent::Button(Button::Action {
-                style: ButtonStyle::Primary,
-                custom_id: format!("{}__label", name),
-                label: Some(name.clone()),
-                disabled: true,
-            }),
-            ActionRowComponent::Button(Button::Action {
-                style: ButtonStyle::Primary,
-                custom_id: format!("{}__min", name),
-                label: Some("<".into()),
-                disabled: *val == min,
-            }),
-            ActionRowComponent::Button(Button::Action {
-                style: ButtonStyle::Secondary,
-                custom_id: format!("{}", name),
-                label: Some(val.to_string()),
-                disabled: false,
-            }),
+        // large steps only if they fit within the range
+        let large = step.saturating_mul(10);
+        let show_large = i64::from(max) - i64::from(min) >= i64::from(large);
+
+        let button = |label: String, delta: i32, disabled: bool| {
             ActionRowComponent::Button(Button::Action {
                 style: ButtonStyle::Primary,
-                custom_id: format!("{}__max", name),
-                label: Some(">".into()),
-                disabled: *val == max,
+                custom_id: CustomId::new(NUMBER, &name)
+                    .arg(&format!("{:+}", delta))
+                    .to_string(),
+                label: Some(label),
+                disabled,
+            })
+        };
+
+        // add components
+        let mut row = Vec::new();
+        if show_large {
+            row.push(button("«".into(), -large, *val == min));
+        } else {
+            row.push(label_button(name.clone(), self.components.len()));
+        }
+        row.push(button("<".into(), -step, *val == min));
+        row.push(ActionRowComponent::Button(Button::Action {
+            style: ButtonStyle::Secondary,
+            custom_id: CustomId::new(NUMBER, &name).to_string(),
+            label: Some(match show_large {
+                true => format!("{}: {}", name, val),
+                false => val.to_string(),
             }),
-        ]));
+            disabled: false,
+        }));
+        row.push(button(">".into(), step, *val == max));
+        if show_large {
+            row.push(button("»".into(), large, *val == max));
+        }
+        self.components.push(ActionRow::new(row));
     }
     pub fn create_join(&mut self, event: &Event, users: &mut Vec<Snowflake<User>>) {
         self.components.push(ActionRow::new(vec![
             event.button(
                 Button::Action {
                     style: ButtonStyle::Success,
-                    custom_id: "join".into(),
+                    custom_id: JOIN_ID.to_string(),
                     label: Some("Join".into()),
                     disabled: false,
                 },
@@ -179,7 +289,7 @@ impl GameMessage {
             event.button(
                 Button::Action {
                     style: ButtonStyle::Danger,
-                    custom_id: "leave".into(),
+                    custom_id: CustomId::new(GAME, "leave").to_string(),
                     label: Some("Leave".into()),
                     disabled: false,
                 },
@@ -204,14 +314,12 @@ impl GameMessage {
         #[allow(unused_assignments)]
         let mut is_done = false;
 
-        if let Some(i) = event.matches(|i| {
-            let s = i.data.custom_id.strip_prefix('#')?;
-            let c = s.chars().next()?;
-            B64_TABLE
-                .iter()
-                .position(|&p| p == c)
-                .filter(|&i| i < count)
-        }) {
+        if let Some(i) = event
+            .custom_id()
+            .filter(|id| id.is(GRID, "pick"))
+            .and_then(|id| decode_index(id.arg?))
+            .filter(|&i| i < count)
+        {
             if selected.contains(&Some(i)) {
                 // we are not done anymore
                 changed = done(selected);
@@ -254,7 +362,9 @@ impl GameMessage {
                                 true => ButtonStyle::Success,
                                 false => ButtonStyle::Secondary,
                             },
-                            custom_id: format!("#{}", B64_TABLE[i]),
+                            custom_id: CustomId::new(GRID, "pick")
+                                .arg(&encode_index(i).to_string())
+                                .to_string(),
                             label: Some((i + 1).to_string()),
                             disabled: !is_pressed && is_done,
                         }));
@@ -271,3 +381,21 @@ impl GameMessage {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_index, encode_index};
+
+    #[test]
+    fn index_round_trip() {
+        for i in 0..64 {
+            assert_eq!(decode_index(&encode_index(i).to_string()), Some(i));
+        }
+    }
+
+    #[test]
+    fn invalid_index() {
+        assert_eq!(decode_index(""), None);
+        assert_eq!(decode_index("-A"), None);
+    }
+}
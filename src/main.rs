@@ -3,14 +3,18 @@
 #![feature(exhaustive_patterns)]
 #![feature(adt_const_params)]
 
-use std::{env, println};
+use std::{env, println, time::Duration};
 
-use discord::command::{Param, StringOption};
-use discord::interaction::{AnyInteraction, CreateReply, InteractionResource, Webhook};
-use discord::request::Bot;
+use discord::command::{IntegerOption, Param, StringOption};
+use discord::interaction::{AnyInteraction, CreateReply, InteractionResource, ReplyFlag, Webhook};
+#[cfg(feature = "leaderboard")]
+use discord::message::{Author, Embed, Field};
+use discord::permissions::Permission;
+use discord::request::{Bot, Request};
 use discord::user;
 use dotenv::dotenv;
 use game::{Game, InteractionDispatcher};
+use tokio::{select, time::interval};
 
 use discord::application::{self, ApplicationResource};
 use discord::command::CommandData;
@@ -19,11 +23,14 @@ use discord::gateway::Gateway;
 use discord::gateway::GatewayEvent;
 use discord::request::Result;
 
+use crate::cah::settings::GuildSettings;
 use crate::cah::CAH;
 
 mod cah;
 mod game;
 
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
 async fn purge(commands: Commands, client: &Bot) -> Result<()> {
     if let Ok(commands) = commands.all(client).await {
         for command in commands {
@@ -33,7 +40,21 @@ async fn purge(commands: Commands, client: &Bot) -> Result<()> {
     Ok(())
 }
 
+async fn create_command(commands: Commands, client: &Bot, data: CommandData) -> Result<()> {
+    match commands.try_create(data) {
+        Ok(request) => {
+            request.request(client).await?;
+        }
+        Err(err) => println!("could not create command: {:?}", err),
+    }
+    Ok(())
+}
+
 async fn on_command(i: AnyInteraction, d: &mut InteractionDispatcher, client: &Bot) -> Result<()> {
+    if let AnyInteraction::Command(command) = &i {
+        game::users::remember(&command.user, None);
+    }
+
     match i {
         AnyInteraction::Command(command) => match command.data.name.as_str() {
             "ping" => {
@@ -43,23 +64,122 @@ async fn on_command(i: AnyInteraction, d: &mut InteractionDispatcher, client: &B
                     .await?;
             }
             "play" => {
-                let game = command.data.options[0].as_string().unwrap();
+                let game = command
+                    .data
+                    .option("game")
+                    .and_then(|o| o.as_string())
+                    .unwrap();
                 let task = match game {
-                    CAH::NAME => CAH::start(command.token, command.user, None),
+                    CAH::NAME => CAH::start(
+                        command.token,
+                        command.user,
+                        command.guild_id,
+                        command.channel_id,
+                        client,
+                        false,
+                    ),
                     _ => panic!("unknown game"),
                 }
                 .await?;
                 d.register(task);
             }
             "playthread" => {
-                let game = command.data.options[0].as_string().unwrap();
+                let game = command
+                    .data
+                    .option("game")
+                    .and_then(|o| o.as_string())
+                    .unwrap();
                 let task = match game {
-                    CAH::NAME => CAH::start(command.token, command.user, Some(client)),
+                    CAH::NAME => CAH::start(
+                        command.token,
+                        command.user,
+                        command.guild_id,
+                        command.channel_id,
+                        client,
+                        true,
+                    ),
                     _ => panic!("unknown game"),
                 }
                 .await?;
                 d.register(task);
             }
+            "stop" => {
+                let content = match d.stop(command.user.id).await {
+                    true => "Your game has been stopped.",
+                    false => "You have not started any game.",
+                };
+
+                command
+                    .token
+                    .reply(
+                        &Webhook,
+                        CreateReply::default()
+                            .content(content.into())
+                            .flags(ReplyFlag::Ephemeral.into()),
+                    )
+                    .await?;
+            }
+            "defaults" => {
+                let content = match command.guild_id {
+                    Some(guild) => {
+                        let option = |name| command.data.option(name);
+                        cah::settings::set(
+                            guild,
+                            GuildSettings {
+                                packs: option("packs")
+                                    .and_then(|o| o.as_string())
+                                    .map(|s| s.split(',').map(|p| p.trim().to_owned()).collect()),
+                                cards: option("cards")
+                                    .and_then(|o| o.as_integer())
+                                    .map(|i| i as i32),
+                                points: option("points")
+                                    .and_then(|o| o.as_integer())
+                                    .map(|i| i as i32),
+                            },
+                        );
+                        "Saved the default game settings for this server."
+                    }
+                    None => "Default game settings can only be set within a server.",
+                };
+
+                command
+                    .token
+                    .reply(
+                        &Webhook,
+                        CreateReply::default()
+                            .content(content.into())
+                            .flags(ReplyFlag::Ephemeral.into()),
+                    )
+                    .await?;
+            }
+            #[cfg(feature = "leaderboard")]
+            "leaderboard" => {
+                let top = match command.guild_id {
+                    Some(guild) => cah::leaderboard::top(guild, 10),
+                    None => Vec::new(),
+                };
+
+                let mut players = top
+                    .into_iter()
+                    .map(|(user, wins)| format!("`{:2}` {}", wins, user))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                if players.is_empty() {
+                    players = "*None.*".into();
+                }
+
+                command
+                    .token
+                    .reply(
+                        &Webhook,
+                        CreateReply::default().embeds(vec![Embed::default()
+                            .author(Author::new(CAH::NAME))
+                            .color(CAH::COLOR)
+                            .fields(vec![Field::new("Leaderboard", players)])]),
+                    )
+                    .await?;
+            }
             _ => {}
         },
         AnyInteraction::Component(comp) => d.dispatch(comp).await,
@@ -75,24 +195,31 @@ async fn run() -> Result<()> {
     let token = env::var("TOKEN").expect("Bot token TOKEN must be set");
 
     // connect
-    let client = Bot::new(token);
+    let client = Bot::try_new(token).expect("Bot token TOKEN is malformed");
     let application = application::Me.get(&client).await?;
 
     // list guilds
     let mut guilds = user::Me.get_guilds(&client).await?;
     println!("GUILDS");
     for guild in guilds.iter_mut() {
-        purge(application.guild_commands(guild), &client).await?;
+        if let Err(err) = application
+            .set_guild_commands(&client, guild, Vec::new())
+            .await
+        {
+            println!("could not clear guild commands: {:?}", err);
+        }
         println!(" - {}", guild.get_field(&client, |g| &g.name).await?);
     }
 
     // create commands
     purge(application.global_commands(), &client).await?;
 
-    application
-        .global_commands()
-        .create(&client, CommandData::new("ping", "Replies with pong!"))
-        .await?;
+    create_command(
+        application.global_commands(),
+        &client,
+        CommandData::new("ping", "Replies with pong!"),
+    )
+    .await?;
 
     // application
     //     .global_commands()
@@ -107,42 +234,89 @@ async fn run() -> Result<()> {
     //     )
     //     .await?;
 
-    application
-        .global_commands()
-        .create(
-            &client,
-            CommandData::new("play", "Start a new game").options(vec![StringOption::new(
-                "game",
-                "What game to play",
-            )
-            .required()
-            .choices(vec![Param::new(CAH::NAME, CAH::NAME)])
-            .into()]),
+    create_command(
+        application.global_commands(),
+        &client,
+        CommandData::new("play", "Start a new game").options(vec![StringOption::new(
+            "game",
+            "What game to play",
         )
-        .await?;
-
-    application
-        .global_commands()
-        .create(
-            &client,
-            CommandData::new("playthread", "Start a new game within a thread").options(vec![
-                StringOption::new("game", "What game to play")
-                    .required()
-                    .choices(vec![Param::new(CAH::NAME, CAH::NAME)])
+        .required()
+        .choices(vec![Param::new(CAH::NAME, CAH::NAME)])
+        .into()]),
+    )
+    .await?;
+
+    create_command(
+        application.global_commands(),
+        &client,
+        CommandData::new("playthread", "Start a new game within a thread").options(vec![
+            StringOption::new("game", "What game to play")
+                .required()
+                .choices(vec![Param::new(CAH::NAME, CAH::NAME)])
+                .into(),
+        ]),
+    )
+    .await?;
+
+    create_command(
+        application.global_commands(),
+        &client,
+        CommandData::new("stop", "Stop the game you started"),
+    )
+    .await?;
+
+    create_command(
+        application.global_commands(),
+        &client,
+        CommandData::new("defaults", "Set the default game settings for this server")
+            .options(vec![
+                StringOption::new("packs", "Comma separated list of packs").into(),
+                IntegerOption::new("cards", "Amount of cards in a hand")
+                    .min_value(5)
+                    .max_value(cah::MAX_HAND as i64)
                     .into(),
-            ]),
-        )
-        .await?;
+                IntegerOption::new("points", "Amount of points needed to win")
+                    .min_value(1)
+                    .into(),
+            ])
+            .default_member_permissions(Permission::ManageGuild.into()),
+    )
+    .await?;
+
+    #[cfg(feature = "leaderboard")]
+    create_command(
+        application.global_commands(),
+        &client,
+        CommandData::new("leaderboard", "Show the players with the most game wins"),
+    )
+    .await?;
 
     // create dispatch
     let mut dispatch = InteractionDispatcher::new();
 
     // gateway
-    let mut gateway = Gateway::connect(&client).await?;
-    while let Some(event) = gateway.next().await {
-        match event {
-            GatewayEvent::InteractionCreate(i) => on_command(i, &mut dispatch, &client).await?,
-            _ => {}
+    let mut gateway = Gateway::connect(
+        &client,
+        Default::default(),
+        Some(&["READY", "INTERACTION_CREATE"][..]),
+        Default::default(),
+    )
+    .await?;
+    let mut ticks = interval(TICK_INTERVAL);
+    loop {
+        select! {
+            event = gateway.next() => {
+                let Some(event) = event else {
+                    break;
+                };
+                match event {
+                    GatewayEvent::Ready(ready) => println!("logged in as {}", ready.user.username),
+                    GatewayEvent::InteractionCreate(i) => on_command(i, &mut dispatch, &client).await?,
+                    _ => {}
+                }
+            }
+            _ = ticks.tick() => dispatch.tick().await,
         }
     }
     gateway.close().await;